@@ -0,0 +1,224 @@
+//! Steering of the computer snakes.
+//!
+//! Like `sim`, nothing here touches the ECS: a bot looks at a `SnakeSim` and
+//! picks the direction of its snake for the next tick, the move itself goes
+//! through `SnakeSim::step` under the same rules as the players.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet, VecDeque};
+use bevy::math::IVec2;
+use serde::{Deserialize, Serialize};
+use crate::sim::{SnakeSim, Walls, UP, DOWN, LEFT, RIGHT};
+
+const DIRECTIONS: [IVec2; 4] = [UP, DOWN, LEFT, RIGHT];
+
+/// how cleverly a bot plays
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Strategy {
+    /// the safe move closest to the fruit
+    #[default]
+    Greedy,
+    /// the shortest path to the fruit
+    AStar,
+    /// the shortest path to the fruit unless it leads into a dead end, then
+    /// the move with the most room
+    FloodFill,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 3] = [
+        Strategy::Greedy,
+        Strategy::AStar,
+        Strategy::FloodFill,
+    ];
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|s| s == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// key of the name in the locale tables, the bots know nothing of
+    /// the screen
+    pub fn label_key(&self) -> &'static str {
+        match self {
+            Strategy::Greedy => "strategy_greedy",
+            Strategy::AStar => "strategy_astar",
+            Strategy::FloodFill => "strategy_flood_fill",
+        }
+    }
+
+    /// the direction snake `index` takes next, straight on when every move
+    /// is deadly
+    pub fn choose(&self, sim: &SnakeSim, index: usize) -> IVec2 {
+        let moves = safe_moves(sim, index);
+        let fallback = moves
+            .first()
+            .map(|(direction, _)| *direction)
+            .unwrap_or(sim.heading(index));
+        let choice = match self {
+            Strategy::Greedy => greedy(sim, &moves),
+            Strategy::AStar => path_to_fruit(sim, index)
+                .or_else(|| greedy(sim, &moves)),
+            Strategy::FloodFill => roomy_path(sim, index, &moves),
+        };
+        choice.unwrap_or(fallback)
+    }
+}
+
+/// the cell one step from `cell`, across the edge through portal walls
+fn next_cell(sim: &SnakeSim, cell: IVec2, direction: IVec2) -> IVec2 {
+    match sim.walls() {
+        Walls::Solid => cell + direction,
+        Walls::Portal => sim.board().wrap(cell + direction),
+    }
+}
+
+/// steps between two cells ignoring what is in the way
+fn distance(sim: &SnakeSim, from: IVec2, to: IVec2) -> i32 {
+    let board = sim.board();
+    let d = (to - from).abs();
+    match sim.walls() {
+        Walls::Solid => d.x + d.y,
+        Walls::Portal => d.x.min(board.cols as i32 - d.x) + d.y.min(board.rows as i32 - d.y),
+    }
+}
+
+/// directions that don't kill snake `index` this tick with the cell each
+/// leads to, a tail about to move away still counts as in the way
+fn safe_moves(sim: &SnakeSim, index: usize) -> Vec<(IVec2, IVec2)> {
+    let head = sim.snake(index).head();
+    DIRECTIONS
+        .into_iter()
+        .filter(|direction| !sim.is_reverse(index, *direction))
+        .map(|direction| (direction, next_cell(sim, head, direction)))
+        .filter(|(_, cell)| sim.is_free(*cell))
+        .collect()
+}
+
+fn greedy(sim: &SnakeSim, moves: &[(IVec2, IVec2)]) -> Option<IVec2> {
+    let fruit = sim.fruit()?;
+    moves
+        .iter()
+        .min_by_key(|(_, cell)| distance(sim, *cell, fruit))
+        .map(|(direction, _)| *direction)
+}
+
+/// first step of the shortest path from the head of snake `index` to the
+/// fruit, found with A*
+fn path_to_fruit(sim: &SnakeSim, index: usize) -> Option<IVec2> {
+    let fruit = sim.fruit()?;
+    let start = sim.snake(index).head();
+    let mut came_from: HashMap<IVec2, IVec2> = HashMap::new();
+    let mut cost: HashMap<IVec2, i32> = HashMap::from([(start, 0)]);
+    let mut open = BinaryHeap::from([Reverse((distance(sim, start, fruit), start.x, start.y))]);
+
+    while let Some(Reverse((_, x, y))) = open.pop() {
+        let cell = IVec2::new(x, y);
+        if cell == fruit {
+            // walk back to the cell next to the head
+            let mut step = cell;
+            while let Some(prev) = came_from.get(&step) {
+                if *prev == start {
+                    return Some(sim.board().offset(start, step));
+                }
+                step = *prev;
+            }
+            return None;
+        }
+        let next_cost = cost[&cell] + 1;
+        for direction in DIRECTIONS {
+            let next = next_cell(sim, cell, direction);
+            if !sim.is_free(next) || cost.get(&next).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+            cost.insert(next, next_cost);
+            came_from.insert(next, cell);
+            open.push(Reverse((next_cost + distance(sim, next, fruit), next.x, next.y)));
+        }
+    }
+    None
+}
+
+/// free cells reachable from `from`, itself included
+fn room(sim: &SnakeSim, from: IVec2) -> usize {
+    let mut seen = HashSet::from([from]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(cell) = queue.pop_front() {
+        for direction in DIRECTIONS {
+            let next = next_cell(sim, cell, direction);
+            if sim.is_free(next) && seen.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    seen.len()
+}
+
+fn roomy_path(sim: &SnakeSim, index: usize, moves: &[(IVec2, IVec2)]) -> Option<IVec2> {
+    let head = sim.snake(index).head();
+    let len = sim.snake(index).len();
+    // chase the fruit while the snake still fits where the path goes
+    if let Some(direction) = path_to_fruit(sim, index) {
+        if room(sim, next_cell(sim, head, direction)) >= len {
+            return Some(direction);
+        }
+    }
+    moves
+        .iter()
+        .max_by_key(|(_, cell)| room(sim, *cell))
+        .map(|(direction, _)| *direction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{Board, FruitKind, StepOutcome};
+
+    fn cells(cells: &[(i32, i32)]) -> Vec<IVec2> {
+        cells.iter().map(|(x, y)| IVec2::new(*x, *y)).collect()
+    }
+
+    /// the move of `strategy` and whether the snake lives through it
+    fn survives(strategy: Strategy, mut sim: SnakeSim) -> bool {
+        let direction = strategy.choose(&sim, 0);
+        sim.step(&[direction]);
+        sim.snake(0).is_alive()
+    }
+
+    #[test]
+    fn turns_away_from_a_wall() {
+        for strategy in Strategy::ALL {
+            // the fruit is behind, straight on is the wall
+            let mut sim = SnakeSim::with_snake(Board::new(9, 15),
+                cells(&[(4, 0), (3, 0), (2, 0)]));
+            sim.set_fruit(Some(IVec2::new(-4, 0)));
+            assert!(survives(strategy, sim), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn turns_away_from_its_body() {
+        for strategy in Strategy::ALL {
+            // the fruit is above, on the other side of the body
+            let mut sim = SnakeSim::with_snake(Board::new(9, 15),
+                cells(&[(0, 0), (-1, 0), (-1, 1), (0, 1), (1, 1), (2, 1)]));
+            sim.set_fruit(Some(IVec2::new(0, 3)));
+            assert!(survives(strategy, sim), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn reaches_the_fruit_on_an_open_board() {
+        let board = Board::new(9, 15);
+        for strategy in Strategy::ALL {
+            let mut sim = SnakeSim::new(board);
+            sim.set_fruit(Some(IVec2::new(-3, 6)));
+            let ate = (0..board.cell_count()).any(|_| {
+                let direction = strategy.choose(&sim, 0);
+                sim.step(&[direction]);
+                sim.snake(0).last_outcome() == StepOutcome::Ate(FruitKind::Apple)
+            });
+            assert!(ate, "{strategy:?}");
+        }
+    }
+}
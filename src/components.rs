@@ -1,25 +1,77 @@
+use std::collections::VecDeque;
 use bevy::prelude::*;
+use crate::{
+    sim::SnakeSim,
+    bot::Strategy,
+    keymap::Action
+};
 
 #[derive(Component)]
 pub struct MainCamera;
 
 #[derive(Debug, Default, Clone, Copy, Component)]
 pub struct SnakeHead {
+    /// the snake in `Simulation`, also the player in a versus game
+    pub index: usize,
     pub direction: IVec2
 }
 
+/// a snake steered by the computer, on its head
+#[derive(Debug, Component)]
+pub struct Bot(pub Strategy);
+
+/// the segment entities of a snake from the head to the tail, on its head
+#[derive(Debug, Default, Component)]
+pub struct SnakeSegments(pub Vec<Entity>);
+
+/// turns waiting for the coming ticks, `move_snake` takes one per move so
+/// quick double turns are not lost
+#[derive(Debug, Default, Component)]
+pub struct InputQueue(pub VecDeque<IVec2>);
+
+impl InputQueue {
+    pub const LEN: usize = 3;
+
+    /// queue `direction` behind the waiting turns, unless it repeats or
+    /// reverses the turn it follows or the queue is full
+    pub fn push(&mut self, direction: IVec2, head: &SnakeHead, sim: &SnakeSim) {
+        // the snake may be held still, it still can't turn into its neck
+        let (last, facing) = match self.0.back() {
+            Some(turn) => (*turn, *turn),
+            None => (head.direction, sim.heading(head.index))
+        };
+        if direction == IVec2::ZERO ||
+            direction == last ||
+            direction == -facing ||
+            self.0.len() >= Self::LEN {
+            return;
+        }
+        self.0.push_back(direction);
+    }
+}
+
 #[derive(Component)]
 pub struct SnakeSegment; 
 
 #[derive(Component)]
-pub struct Fruit; 
+pub struct Fruit;
 
+/// score of the snake with this index in the play bar
 #[derive(Component)]
-pub struct Score(pub u16); 
+pub struct Score(pub usize);
+
+/// progress toward the goal of a campaign stage in the play bar
+#[derive(Component)]
+pub struct GoalText;
 
 #[derive(Debug, Component, Default, Copy, Clone, PartialEq)]
 pub struct Position(pub IVec2);
 
+/// the cell of a snake segment before the last move, smooth movement slides
+/// the segment from there to its `Position`
+#[derive(Debug, Component, Default, Copy, Clone, PartialEq)]
+pub struct LastPosition(pub IVec2);
+
 #[derive(Component)]
 pub struct OnMainMenuScreen;
 
@@ -27,10 +79,71 @@ pub struct OnMainMenuScreen;
 pub struct OnPauseMenuScreen;
 
 #[derive(Component)]
-pub struct SelectedOption;
+pub struct OnPlayMenuScreen;
 
 #[derive(Component)]
-pub struct OnPlayMenuScreen;
+pub struct OnHighScoreScreen;
+
+#[derive(Component)]
+pub struct OnGameOverScreen;
+
+#[derive(Component)]
+pub struct OnBoardMenuScreen;
+
+#[derive(Component)]
+pub struct OnSettingsScreen;
+
+#[derive(Component)]
+pub struct OnLevelCompleteScreen;
+
+#[derive(Component)]
+pub struct OnControlsScreen;
+
+#[derive(Component)]
+pub struct OnRoundOverScreen;
+
+#[derive(Component)]
+pub struct OnReplaysScreen;
+
+/// node holding a row per kept replay on the replays screen
+#[derive(Component)]
+pub struct ReplayList;
+
+/// result of an export or an import on the replays screen
+#[derive(Component)]
+pub struct ReplaysHint;
+
+/// controls over a replay playing back
+#[derive(Component)]
+pub struct OnPlaybackBar;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum PlaybackButton {
+    Pause,
+    Step,
+    Speed,
+    Exit,
+}
+
+/// text of a playback button or of the tick count, refreshed as the
+/// playback goes
+#[derive(Debug, Component)]
+pub struct PlaybackText(pub Option<PlaybackButton>);
+
+/// bar to click to jump through a playback, the fill shows how far it got
+#[derive(Component)]
+pub struct Scrubber;
+
+#[derive(Component)]
+pub struct ScrubberFill;
+
+/// text of a button showing the keys bound to an action
+#[derive(Debug, Component)]
+pub struct BindingText(pub Action);
+
+/// prompt and conflicts on the controls screen
+#[derive(Component)]
+pub struct ControlsHint;
 
 #[derive(Debug, Component)]
 pub struct ToSelectOption;
@@ -38,5 +151,127 @@ pub struct ToSelectOption;
 #[derive(Debug, Component)]
 pub struct MenuItems;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum MenuButtonAction {
+    New,
+    /// the game saved when the last session quit
+    Continue,
+    Pause,
+    Resume,
+    Quit,
+    Difficulty,
+    Accelerate,
+    /// slide the snakes between ticks instead of stepping them
+    Smooth,
+    /// the next look of the board and the menus
+    Theme,
+    /// the settings screen, back to the menu it was opened from
+    Settings,
+    /// grid lines on the board
+    Grid,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    /// the next language of the menus
+    Language,
+    HighScores,
+    Back,
+    Mute,
+    MainMenu,
+    Board,
+    BoardPreset,
+    BoardCols,
+    BoardRows,
+    /// turn the board on its side in a landscape window
+    TurnLandscape,
+    Walls,
+    Level,
+    Campaign,
+    NextStage,
+    Controls,
+    Rebind(Action),
+    ResetControls,
+    /// pick the gamepad of a player
+    Gamepad(usize),
+    Versus,
+    Bots,
+    BotStrategy,
+    /// random, today's or the last game's seed for the next games
+    Seed,
+    /// play the seed of the game just over again
+    PlaySeed,
+    Replays,
+    PlayReplay(usize),
+    ExportReplay(usize),
+    ImportReplays,
+}
+
+/// text of a button showing the current value of a setting
+#[derive(Debug, Component)]
+pub struct OptionText(pub MenuButtonAction);
+
 #[derive(Component)]
 pub struct OnGameScreen;
+
+/// walls, grid lines and obstacles, drawn again when the window is resized
+#[derive(Component)]
+pub struct Scenery;
+
+#[derive(Component)]
+pub struct BackgroundMusic;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{Board, UP, DOWN, LEFT, RIGHT};
+
+    /// the default snake, facing right, and its head moving `direction`
+    fn snake(direction: IVec2) -> (SnakeHead, SnakeSim) {
+        (SnakeHead { index: 0, direction }, SnakeSim::new(Board::new(9, 15)))
+    }
+
+    #[test]
+    fn no_reversal_before_the_first_move() {
+        // held still, the neck is still behind the head
+        let (head, sim) = snake(IVec2::ZERO);
+        let mut queue = InputQueue::default();
+        queue.push(LEFT, &head, &sim);
+        assert!(queue.0.is_empty());
+        queue.push(UP, &head, &sim);
+        assert_eq!(queue.0, [UP]);
+    }
+
+    #[test]
+    fn no_reversal_of_the_last_queued_turn() {
+        let (head, sim) = snake(RIGHT);
+        let mut queue = InputQueue::default();
+        queue.push(UP, &head, &sim);
+        queue.push(DOWN, &head, &sim);
+        assert_eq!(queue.0, [UP]);
+        // back the way the snake goes now is fine after a turn
+        queue.push(LEFT, &head, &sim);
+        assert_eq!(queue.0, [UP, LEFT]);
+    }
+
+    #[test]
+    fn a_repeated_turn_is_dropped() {
+        let (head, sim) = snake(RIGHT);
+        let mut queue = InputQueue::default();
+        queue.push(RIGHT, &head, &sim);
+        assert!(queue.0.is_empty());
+        queue.push(UP, &head, &sim);
+        queue.push(UP, &head, &sim);
+        assert_eq!(queue.0, [UP]);
+    }
+
+    #[test]
+    fn the_queue_holds_at_most_len_turns() {
+        let (head, sim) = snake(RIGHT);
+        let mut queue = InputQueue::default();
+        for direction in [UP, LEFT, DOWN, RIGHT] {
+            queue.push(direction, &head, &sim);
+        }
+        assert_eq!(queue.0.len(), InputQueue::LEN);
+        assert_eq!(queue.0, [UP, LEFT, DOWN]);
+    }
+}
@@ -1,20 +1,44 @@
+use std::time::Duration;
 use bevy::prelude::*;
-use rand::prelude::*;
 use crate::{
     GameAssets,
     GameState,
+    MenuState,
     despawn_screen,
-    resources::{SnakeSegments, GameSetting}, 
+    resources::{
+        GameSetting, Simulation, TickTimer,
+        HighScores, HighScoreEntry, PlayTime, LastGame,
+        Campaign, CampaignProgress, SpeedBoost, GameMode, RoundResult, Demo, GameRng,
+        SettingsReturn
+    }, 
     components::*, 
     events::*,
+    theme::{Palette, ThemeRole},
+    locale::Locale,
     prelude::*,
-    graphics::to_game_xyz
+    graphics::{
+        to_game_xyz, head_texture, tail_texture, body_texture, fruit_texture, snake_tint
+    },
+    sim::{SnakeSim, StepOutcome, DeathCause, Walls, FruitKind},
+    storage::unix_now,
+    level::Level,
+    replay::{Replay, Replays, Recording, Playback},
+    save::{SavedGame, SaveSlot}
 };
 
 pub fn new_game(
     commands: Commands,
     query: Query<Entity, With<OnGameScreen>>,
     mut game_state: ResMut<NextState<GameState>>,
+    mut play_time: ResMut<PlayTime>,
+    mut speed_boost: ResMut<SpeedBoost>,
+    mut rng: ResMut<GameRng>,
+    mut recording: ResMut<Recording>,
+    playback: Option<ResMut<Playback>>,
+    mut game_settings: ResMut<GameSetting>,
+    campaign: Res<Campaign>,
+    game_mode: Res<GameMode>,
+    demo: Res<Demo>,
     // mut touch_evr: EventReader<TouchInput>,
     // mut touch_start: ResMut<TouchPosition>
 ) {
@@ -25,47 +49,335 @@ pub fn new_game(
     //     }
     // }
     despawn_screen(query, commands);
+    play_time.0 = Duration::ZERO;
+    speed_boost.0 = None;
+    // the demo plays a different game every time
+    let seed = game_settings.seed
+        .filter(|_| !demo.playing)
+        .unwrap_or_else(GameRng::random_seed);
+    *rng = GameRng::new(seed);
+    info!("seed {seed}");
+    // a playback starts over from its first tick and isn't recorded again
+    recording.0 = match playback {
+        Some(mut playback) => {
+            playback.tick = 0;
+            // on the board it started on, the turns come again
+            let settings = playback.replay.settings(&game_settings);
+            game_settings.play_with(settings);
+            None
+        },
+        None if demo.playing => None,
+        None => Some(Replay::new(seed, &game_settings, &campaign, *game_mode))
+    };
     game_state.set(GameState::Play);
 }
 
 pub fn init_snake(
     mut commands: Commands,
-    mut segments: ResMut<SnakeSegments>,
+    mut sim: ResMut<Simulation>,
+    game_assets: Res<GameAssets>,
+    mut game_settings: ResMut<GameSetting>,
+    mut campaign: ResMut<Campaign>,
+    game_mode: Res<GameMode>,
+    demo: Res<Demo>,
+    levels: Res<Assets<Level>>
+) {
+    let handle = match campaign.stage {
+        Some(stage) => game_assets.campaign.get(stage),
+        None => game_settings.level.and_then(|i| game_assets.levels.get(i))
+    };
+    let level = handle.and_then(|handle| levels.get(handle));
+    campaign.goal = campaign.stage
+        .and(level)
+        .and_then(|level| level.goal);
+    let rivals = game_mode.players() - 1 + game_settings.bots;
+    let level_sim = level.and_then(|level| {
+        let sim = level.sim(rivals);
+        if sim.is_none() {
+            warn!("no room for {rivals} more snakes on the level, playing on an empty board");
+        }
+        // on its side in a turned landscape window
+        sim.map(|sim| if game_settings.turned { sim.turned(true) } else { sim })
+    });
+    let new_sim = match level_sim {
+        Some(level_sim) => {
+            // the level decides the board size, for this game only
+            let settings = GameSetting { board: level_sim.board(), ..game_settings.clone() };
+            game_settings.play_with(settings);
+            level_sim
+        },
+        None => {
+            if handle.is_some() && level.is_none() {
+                warn!("level is not loaded, playing on an empty board");
+            }
+            match *game_mode {
+                GameMode::Solo => SnakeSim::new(game_settings.board),
+                GameMode::Versus => SnakeSim::versus(game_settings.board),
+            }
+            .with_free_rivals(game_settings.bots, 3)
+        }
+    };
+    *sim = Simulation(new_sim.with_walls(game_settings.walls));
+    spawn_snakes(&mut commands, &sim.0, &game_assets, &game_settings, *game_mode, &demo);
+}
+
+/// the sprites of every snake of `sim`
+fn spawn_snakes(
+    commands: &mut Commands,
+    sim: &SnakeSim,
+    game_assets: &Res<GameAssets>,
+    game_settings: &GameSetting,
+    game_mode: GameMode,
+    demo: &Demo
+) {
+    for index in 0..sim.snakes().len() {
+        // a bot that died before the game was saved stays off the board
+        if !sim.snake(index).is_alive() {
+            continue;
+        }
+        let head = spawn_snake(commands, index, sim, game_assets, game_settings);
+        // the snakes after the players' are the computer's, so is the
+        // player's in the demo
+        if demo.playing && index == 0 {
+            commands.entity(head).insert(Bot(Demo::STRATEGY));
+        }
+        else if index >= game_mode.players() {
+            commands.entity(head).insert(Bot(game_settings.bot_strategy));
+        }
+    }
+}
+
+/// put the saved game back where it was left, the snakes wait for a
+/// direction like after a pause
+pub fn continue_game(
+    commands: Commands,
+    query: Query<Entity, With<OnGameScreen>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut slot: ResMut<SaveSlot>,
+    mut sim: ResMut<Simulation>,
+    mut play_time: ResMut<PlayTime>,
+    mut speed_boost: ResMut<SpeedBoost>,
+    mut rng: ResMut<GameRng>,
+    mut recording: ResMut<Recording>,
+    mut timer: ResMut<TickTimer>,
+    mut game_settings: ResMut<GameSetting>,
+    mut campaign: ResMut<Campaign>,
+    mut game_mode: ResMut<GameMode>,
+    game_assets: Res<GameAssets>,
+    levels: Res<Assets<Level>>
+) {
+    let Some(saved) = slot.take() else {
+        game_state.set(GameState::Menu);
+        menu_state.set(MenuState::Main);
+        return;
+    };
+    info!("continue the saved game of seed {}", saved.seed);
+    despawn_screen(query, commands);
+    let settings = saved.settings(&game_settings);
+    game_settings.play_with(settings);
+    *game_mode = saved.game_mode();
+    campaign.stage = saved.stage;
+    campaign.goal = saved.stage
+        .and_then(|stage| game_assets.campaign.get(stage))
+        .and_then(|handle| levels.get(handle))
+        .and_then(|level| level.goal);
+    play_time.0 = saved.play_time();
+    speed_boost.0 = saved.speed_boost.map(|left| {
+        let mut boost = Timer::new(SpeedBoost::DURATION, TimerMode::Once);
+        boost.set_elapsed(SpeedBoost::DURATION.saturating_sub(Duration::from_secs_f32(left)));
+        boost
+    });
+    *rng = GameRng::at(saved.seed, saved.rng_position);
+    recording.0 = saved.recording;
+    timer.0 = Timer::new(game_settings.difficulty.tick(), TimerMode::Repeating);
+    *sim = Simulation(saved.sim);
+    game_state.set(GameState::Play);
+}
+
+pub fn continue_snakes(
+    mut commands: Commands,
+    sim: Res<Simulation>,
     game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    game_mode: Res<GameMode>,
+    demo: Res<Demo>
+) {
+    spawn_snakes(&mut commands, &sim.0, &game_assets, &game_settings, *game_mode, &demo);
+}
+
+/// keep the game in progress when the app quits, from the pause menu or by
+/// closing the window, the demo and a playback are not kept
+pub fn save_game(
+    game_state: Res<State<GameState>>,
+    menu_state: Res<State<MenuState>>,
+    settings_return: Res<SettingsReturn>,
+    demo: Res<Demo>,
+    playback: Option<Res<Playback>>,
+    sim: Res<Simulation>,
+    play_time: Res<PlayTime>,
+    speed_boost: Res<SpeedBoost>,
+    rng: Res<GameRng>,
+    recording: Res<Recording>,
+    game_settings: Res<GameSetting>,
+    campaign: Res<Campaign>,
+    game_mode: Res<GameMode>,
+    mut slot: ResMut<SaveSlot>
+) {
+    let in_game = in_game(*game_state.get(), *menu_state.get(), settings_return.0);
+    let players_alive = sim.0.snakes()[..game_mode.players()]
+        .iter()
+        .all(|snake| snake.is_alive());
+    if !in_game || !players_alive || demo.playing || playback.is_some() {
+        return;
+    }
+    info!("saving the game of seed {}", rng.seed);
+    slot.0 = Some(SavedGame {
+        sim: sim.0.clone(),
+        play_time: play_time.0.as_secs_f32(),
+        seed: rng.seed,
+        rng_position: rng.position(),
+        speed_boost: speed_boost.0
+            .as_ref()
+            .map(|timer| timer.remaining_secs()),
+        level: game_settings.level,
+        stage: campaign.stage,
+        versus: *game_mode == GameMode::Versus,
+        bot_strategy: game_settings.bot_strategy,
+        difficulty: game_settings.difficulty,
+        accelerate: game_settings.accelerate,
+        turned: game_settings.turned,
+        recording: recording.0.clone(),
+    });
+    slot.save();
+}
+
+/// true while a game is played or paused, the settings and the controls
+/// opened from the pause menu still hold it
+fn in_game(game_state: GameState, menu_state: MenuState, settings_return: MenuState) -> bool {
+    let menu_state = match menu_state {
+        MenuState::Settings | MenuState::Controls => settings_return,
+        menu_state => menu_state,
+    };
+    matches!(game_state, GameState::Play | GameState::Menu) &&
+        matches!(menu_state, MenuState::Play | MenuState::Pause)
+}
+
+pub fn init_tick(
+    mut timer: ResMut<TickTimer>,
+    game_settings: Res<GameSetting>
+) {
+    timer.0 = Timer::new(game_settings.difficulty.tick(), TimerMode::Repeating);
+}
+
+pub fn tick_timer(
+    time: Res<Time>,
+    mut timer: ResMut<TickTimer>,
+    playback: Option<Res<Playback>>
+) {
+    let Some(playback) = playback else {
+        timer.0.tick(time.delta());
+        return;
+    };
+    if playback.forced() {
+        let remaining = timer.0.remaining();
+        timer.0.tick(remaining);
+    }
+    else if playback.running() {
+        timer.0.tick(time.delta().mul_f32(playback.speed()));
+    }
+}
+
+pub fn play_time(
+    time: Res<Time>,
+    mut play_time: ResMut<PlayTime>
+) {
+    play_time.0 += time.delta();
+}
+
+/// run condition of the systems moving the snake
+pub fn on_tick(timer: Res<TickTimer>) -> bool {
+    timer.0.just_finished()
+}
+
+pub fn speed_up(
+    mut timer: ResMut<TickTimer>,
+    sim: Res<Simulation>,
+    speed_boost: Res<SpeedBoost>,
     game_settings: Res<GameSetting>
 ) {
-    spawn_snake(&mut commands, &mut segments, &game_assets, &game_settings);
+    let mut tick = game_settings.difficulty
+        .tick_for(sim.0.longest(), game_settings.accelerate);
+    if speed_boost.0.is_some() {
+        tick = tick.mul_f32(SpeedBoost::FACTOR);
+    }
+    timer.0.set_duration(tick);
+}
+
+/// run out the boost of a speed fruit
+pub fn speed_boost(
+    time: Res<Time>,
+    mut speed_boost: ResMut<SpeedBoost>
+) {
+    let Some(timer) = speed_boost.0.as_mut() else {
+        return;
+    };
+    if timer.tick(time.delta()).finished() {
+        speed_boost.0 = None;
+    }
 }
 
 pub fn init_wall(
     mut commands: Commands,
-    game_settings: Res<GameSetting>
+    sim: Res<Simulation>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>
 ) {
-    draw_wall(&mut commands, &game_settings);
+    draw_wall(&mut commands, &game_settings, &palette);
+    draw_obstacles(&mut commands, &sim.0, &game_settings, &palette);
 }
 
 pub fn init_fruit(
     mut commands: Commands,
-    positions: Query<&Position, With<SnakeSegment>>,
+    mut sim: ResMut<Simulation>,
+    mut rng: ResMut<GameRng>,
     game_assets: Res<GameAssets>,
     game_settings: Res<GameSetting>
 ) {
-    spawn_fruit(&mut commands, positions, &game_assets, &game_settings);
+    match sim.0.fruit() {
+        // placed by the level
+        Some(cell) => spawn_fruit_at(&mut commands, cell, sim.0.fruit_kind(),
+            &game_assets, &game_settings),
+        None => spawn_fruit(&mut commands, &mut sim.0, &mut rng, &game_assets, &game_settings)
+    }
 }
 
 pub fn spawn_fruit(
     commands: &mut Commands,
-    positions: Query<&Position, With<SnakeSegment>>,
+    sim: &mut SnakeSim,
+    rng: &mut GameRng,
     game_assets: &Res<GameAssets>,
     game_settings: &Res<GameSetting>
 ) {
-    let translation = generate_position(positions);
+    // the board is full
+    let Some(translation) = sim.spawn_fruit(&mut rng.rng) else {
+        return;
+    };
+    spawn_fruit_at(commands, translation, sim.fruit_kind(), game_assets, game_settings);
+}
+
+fn spawn_fruit_at(
+    commands: &mut Commands,
+    translation: IVec2,
+    kind: FruitKind,
+    game_assets: &GameAssets,
+    game_settings: &GameSetting
+) {
     commands.spawn((
         SpriteBundle {
-            texture: game_assets.apple.clone(),
+            texture: fruit_texture(kind, game_assets),
             transform: Transform::from_translation(
-                to_game_xyz(translation.x, translation.y, 1,
-                    game_settings.snake_width)
+                to_game_xyz(translation.x, translation.y, 1, game_settings)
                 )
                 .with_scale(Vec3::splat(game_settings.game_scale)),
             ..default()
@@ -76,372 +388,635 @@ pub fn spawn_fruit(
     ));
 }
 
-fn generate_position(
-    positions: Query<&Position, With<SnakeSegment>>,
-) -> IVec2 {
-    let mut result;
-    let board_rows = BOARD_ROWS as i32 / 2;
-    let board_cols = BOARD_COLS as i32 / 2;
-    let mut rng = thread_rng();
-    result = IVec2::new(
-        rng.gen_range(-board_cols..=board_cols),
-        rng.gen_range(-board_rows..=board_rows)
-    );
-
-    if positions.is_empty() {
-        let pos: Vec<(i32, i32)> = vec![(-1, 0), (0, 0), (1, 0)];
-        if pos.contains(&result.into()) {
-            result = generate_position(positions);
-        }
+/// take a bonus fruit that ran out of moves off the board, a new fruit
+/// replaces it
+pub fn expire_fruit(
+    mut commands: Commands,
+    fruits: Query<Entity, With<Fruit>>,
+    sim: Res<Simulation>,
+    mut spawn_fruit_evw: EventWriter<SpawnFruitEvent>
+) {
+    if !sim.0.fruit_expired() {
+        return;
     }
-    else if positions.iter().any(|p| p.0 == result) {
-        result = generate_position(positions);
+    for entity in fruits.iter() {
+        commands.entity(entity).despawn_recursive();
     }
-
-    result
+    spawn_fruit_evw.send(SpawnFruitEvent);
 }
 
 pub fn draw_wall(
     commands: &mut Commands,
-    game_settings: &Res<GameSetting>
+    game_settings: &GameSetting,
+    palette: &Palette
 ) {
-    let window_width = BOARD_COLS as f32 * game_settings.snake_width +
-        2. * WALL_WIDTH;
-    let window_height = BOARD_ROWS as f32 * game_settings.snake_width +
-        2. * WALL_WIDTH + BOARD_OFFSET_Y;
-    let board_width = BOARD_COLS as f32 * game_settings.snake_width;
-    let board_height = BOARD_ROWS as f32 * game_settings.snake_width;
-    // top wall
-    commands.spawn((SpriteBundle {
-            sprite: Sprite {
-                color: WALL_COLOR,
-                custom_size: Some(Vec2::new(window_width, WALL_WIDTH)),
-                ..default()
-            },
-            transform: Transform::from_translation(
-                Vec3::new(0., window_height/2. - WALL_WIDTH/2. - BOARD_OFFSET_Y, 1.)),
-            ..default()
-        },
-        OnGameScreen,
-    ));
-    // bottom wall
-    commands.spawn((SpriteBundle {
-            sprite: Sprite {
-                color: WALL_COLOR,
-                custom_size: Some(Vec2::new(window_width, WALL_WIDTH)),
-                ..default()
+    let board = game_settings.board;
+    let board_width = board.cols as f32 * game_settings.snake_width;
+    let board_height = board.rows as f32 * game_settings.snake_width;
+    let window_width = board_width + 2. * WALL_WIDTH;
+    let offset = game_settings.board_offset();
+    let walls = [
+        // top wall
+        (
+            Vec2::new(window_width, WALL_WIDTH),
+            Vec2::new(0., (board_height + WALL_WIDTH)/2.)
+        ),
+        // bottom wall
+        (
+            Vec2::new(window_width, WALL_WIDTH),
+            Vec2::new(0., -(board_height + WALL_WIDTH)/2.)
+        ),
+        // left wall
+        (
+            Vec2::new(WALL_WIDTH, board_height),
+            Vec2::new((-window_width + WALL_WIDTH)/2., 0.)
+        ),
+        // right wall
+        (
+            Vec2::new(WALL_WIDTH, board_height),
+            Vec2::new((window_width - WALL_WIDTH)/2., 0.)
+        ),
+    ];
+    for (size, center) in walls {
+        let center = center + offset;
+        match game_settings.walls {
+            Walls::Solid => {
+                commands.spawn((SpriteBundle {
+                        sprite: Sprite {
+                            color: palette.wall,
+                            custom_size: Some(size),
+                            ..default()
+                        },
+                        transform: Transform::from_translation(center.extend(1.)),
+                        ..default()
+                    },
+                    OnGameScreen,
+                    ThemeRole::Wall,
+                    Scenery,
+                ));
             },
-            transform: Transform::from_translation(
-                Vec3::new(0., -window_height/2. + WALL_WIDTH/2., 1.)),
-            ..default()
-        },
-        OnGameScreen,
-    ));
-    // left wall
-    commands.spawn((SpriteBundle {
+            Walls::Portal => {
+                // a dash in front of every cell the snake can pass through
+                let horizontal = size.x > size.y;
+                let cells = if horizontal { board.cols } else { board.rows };
+                let dash = game_settings.snake_width * 0.6;
+                for i in 0..cells {
+                    let along = game_settings.snake_width *
+                        (i as f32 - (cells as f32 - 1.) / 2.);
+                    let (dash_size, offset) = if horizontal {
+                        (Vec2::new(dash, WALL_WIDTH), Vec2::new(along, 0.))
+                    } else {
+                        (Vec2::new(WALL_WIDTH, dash), Vec2::new(0., along))
+                    };
+                    commands.spawn((SpriteBundle {
+                            sprite: Sprite {
+                                color: palette.portal,
+                                custom_size: Some(dash_size),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(
+                                (center + offset).extend(1.)),
+                            ..default()
+                        },
+                        OnGameScreen,
+                        ThemeRole::Portal,
+                        Scenery,
+                    ));
+                }
+            }
+        }
+    }
+
+    // draw board grid
+    if !game_settings.grid {
+        return;
+    }
+    let line_color = palette.grid;
+    for y in 0..=board.rows {
+        commands.spawn((SpriteBundle {
             sprite: Sprite {
-                color: WALL_COLOR,
-                custom_size: Some(Vec2::new(WALL_WIDTH, board_height)),
+                color: line_color,
+                custom_size: Some(Vec2::new(board_width, 2.)),
                 ..default()
             },
             transform: Transform::from_translation(
                 Vec3::new(
-                    (-window_width + WALL_WIDTH)/2., 
-                    -BOARD_OFFSET_Y/2.,
-                    1.
-                )
-            ),
-            ..default()
-        },
-        OnGameScreen,
-    ));
-    // right wall
-    commands.spawn((SpriteBundle {
-            sprite: Sprite {
-                color: WALL_COLOR,
-                custom_size: Some(Vec2::new(WALL_WIDTH, board_height)),
+                    offset.x, 
+                    offset.y - board_height/2. +
+                        game_settings.snake_width * y as f32,
+                    0.
+                )),
                 ..default()
             },
-            transform: Transform::from_translation(
-                Vec3::new(
-                    (window_width - WALL_WIDTH)/2., 
-                    -BOARD_OFFSET_Y/2.,
-                    1.
-                )
-            ),
-            ..default()
-        },
-        OnGameScreen,
-    ));
-
-    // draw board grid
-    let b_rows = BOARD_ROWS as i32 / 2;
-    let b_cols = BOARD_COLS as i32 / 2;
-    let line_color = Color::DARK_GRAY;
-    for y in -b_rows..b_rows+2 {
+            OnGameScreen,
+            ThemeRole::Grid,
+            Scenery,
+        ));
+    }
+    for x in 0..=board.cols {
         commands.spawn((SpriteBundle {
             sprite: Sprite {
                 color: line_color,
-                custom_size: Some(Vec2::new(board_width, 2.)),
+                custom_size: Some(Vec2::new(2., board_height)),
                 ..default()
             },
             transform: Transform::from_translation(
                 Vec3::new(
-                    0., 
-                    -BOARD_OFFSET_Y/2. + game_settings.snake_width * (-0.5 + y as f32),
+                    offset.x - board_width/2. + game_settings.snake_width * x as f32,
+                    offset.y,
                     0.
                 )),
                 ..default()
             },
             OnGameScreen,
+            ThemeRole::Grid,
+            Scenery,
         ));
-        for x in -b_cols..b_cols+2 {
-            commands.spawn((SpriteBundle {
+    }
+}
+
+pub fn draw_obstacles(
+    commands: &mut Commands,
+    sim: &SnakeSim,
+    game_settings: &GameSetting,
+    palette: &Palette
+) {
+    for cell in sim.obstacles() {
+        commands.spawn((SpriteBundle {
                 sprite: Sprite {
-                    color: line_color,
-                    custom_size: Some(Vec2::new(2., board_height)),
+                    color: palette.wall,
+                    custom_size: Some(Vec2::splat(game_settings.snake_width * 0.9)),
                     ..default()
                 },
                 transform: Transform::from_translation(
-                    Vec3::new(
-                        game_settings.snake_width * (-0.5 + x as f32),
-                        -BOARD_OFFSET_Y/2.,
-                        0.
-                    )),
-                    ..default()
-                },
-                OnGameScreen,
-            ));
-        }
+                    to_game_xyz(cell.x, cell.y, 1, game_settings)),
+                ..default()
+            },
+            OnGameScreen,
+            ThemeRole::Wall,
+            Scenery,
+        ));
     }
 }
 
 fn spawn_snake(
     commands: &mut Commands,
-    segments: &mut ResMut<SnakeSegments>,
+    index: usize,
+    sim: &SnakeSim,
     game_assets: &Res<GameAssets>,
-    game_settings: &Res<GameSetting>
-) {
-    segments.0 = vec![
-        commands.spawn((
-            SpriteBundle {
-                texture: game_assets.head_right.clone(),
-                transform: Transform::from_translation(
-                    to_game_xyz(1, 0, 1, game_settings.snake_width)
-                )
-                .with_scale(Vec3::splat(game_settings.game_scale)),
-                ..default()
-            },
-            OnGameScreen,
-            SnakeHead::default(),
-            SnakeSegment,
-            Position(IVec2::new(1, 0)),
-        )).id(),
-        commands.spawn((
-            SpriteBundle {
-                texture: game_assets.body_horizontal.clone(),
-                transform: Transform::from_translation(
-                    to_game_xyz(0, 0, 1, game_settings.snake_width)
-                )
-                .with_scale(Vec3::splat(game_settings.game_scale)),
-                ..default()
-            },
-            OnGameScreen,
-            SnakeSegment,
-            Position(IVec2::new(0, 0)),
-        ))
-        .id(),
-        commands.spawn((
-            SpriteBundle {
-                texture: game_assets.tail_left.clone(),
-                transform: Transform::from_translation(
-                    to_game_xyz(-1, 0, 1, game_settings.snake_width)
-                )
-                .with_scale(Vec3::splat(game_settings.game_scale)),
-                ..default()
-            },
-            OnGameScreen,
-            SnakeSegment,
-            Position(IVec2::new(-1, 0)),
-        ))
-        .id(),
-    ];
+    game_settings: &GameSetting
+) -> Entity {
+    let body = sim.snake(index).body();
+    let segments: Vec<Entity> = body
+        .iter()
+        .enumerate()
+        .map(|(i, cell)| {
+            let texture = if i == 0 {
+                head_texture(sim.heading(index), game_assets)
+            }
+            else if i == body.len() - 1 {
+                tail_texture(body[i-1], *cell, sim.board(), game_assets)
+            }
+            else {
+                body_texture(body[i-1], *cell, body[i+1], sim.board(), game_assets)
+            };
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: snake_tint(index),
+                        ..default()
+                    },
+                    texture: texture.unwrap_or_default(),
+                    transform: Transform::from_translation(
+                        to_game_xyz(cell.x, cell.y, 1, game_settings)
+                    )
+                    .with_scale(Vec3::splat(game_settings.game_scale)),
+                    ..default()
+                },
+                OnGameScreen,
+                SnakeSegment,
+                Position(*cell),
+                LastPosition(*cell),
+            ))
+            .id()
+        })
+        .collect();
+    let head = segments[0];
+    commands.entity(head).insert((
+        SnakeHead { index, ..default() },
+        InputQueue::default(),
+        SnakeSegments(segments)
+    ));
+    head
+}
+
+/// the directions of the snakes on this tick, from their input queues
+fn next_directions(
+    heads: &mut Query<(&mut SnakeHead, &mut InputQueue, &mut SnakeSegments)>,
+    sim: &SnakeSim
+) -> Vec<IVec2> {
+    // one buffered turn per move and snake, checked against the way its head
+    // faces now
+    let mut directions = vec![IVec2::ZERO; sim.snakes().len()];
+    for (mut head, mut queue, _) in heads.iter_mut() {
+        if let Some(turn) = queue.0.pop_front() {
+            if !sim.is_reverse(head.index, turn) {
+                head.direction = turn;
+            }
+        }
+        directions[head.index] = head.direction;
+    }
+    // the first snake to move sets the others off, so nobody can wait at the
+    // start or after a pause
+    if directions.iter().any(|direction| *direction != IVec2::ZERO) {
+        for (mut head, _, _) in heads.iter_mut() {
+            if head.direction == IVec2::ZERO {
+                head.direction = sim.heading(head.index);
+                directions[head.index] = head.direction;
+            }
+        }
+    }
+    directions
 }
 
 pub fn move_snake(
-    heads: Query<(Entity, &SnakeHead)>,
-    mut bodies: Query<&mut Position>,
-    segments: ResMut<SnakeSegments>,
+    mut commands: Commands,
+    mut heads: Query<(&mut SnakeHead, &mut InputQueue, &mut SnakeSegments)>,
+    mut bodies: Query<(&mut Position, &mut LastPosition), With<SnakeSegment>>,
+    mut sim: ResMut<Simulation>,
+    mut recording: ResMut<Recording>,
+    playback: Option<ResMut<Playback>>,
+    game_mode: Res<GameMode>,
     mut gamover_evw: EventWriter<GameOverEvent>,
 ) {
-    let mut positions: Vec<Position> = Vec::new();
-    segments
-        .0
-        .iter()
-        .for_each(|e| {
-            if let Ok(pos) = bodies.get_mut(*e) {
-                positions.push(*pos);
-            }
-        });
- 
-    let (head_id, head) = heads.single();
-    if let Ok(mut head_pos) = bodies.get_mut(head_id) {
-        let (nx, ny) = (
-            head_pos.0.x + head.direction.x,
-            head_pos.0.y + head.direction.y
-        );
-        // collidle with self
-        if positions.iter().skip(1)
-            .any(|p| p.0 == IVec2::new(nx, ny)) {
-                gamover_evw.send(GameOverEvent);
-                return;
+    // a snake that doesn't move this tick stays where it is
+    for (pos, mut last) in bodies.iter_mut() {
+        last.0 = pos.0;
+    }
+    if let Some(mut playback) = playback {
+        let mut directions = playback.next();
+        directions.resize(sim.0.snakes().len(), IVec2::ZERO);
+        for (mut head, _, _) in heads.iter_mut() {
+            head.direction = directions[head.index];
         }
-        
-        // collide with wall
-        let board_rows = BOARD_ROWS as i32 / 2;
-        let board_cols = BOARD_COLS as i32 / 2;
-        if nx < -board_cols || board_cols < nx ||
-            ny < -board_rows || board_rows < ny {
-                gamover_evw.send(GameOverEvent);
-                return;
+        sim.0.step(&directions);
+    }
+    else {
+        let directions = next_directions(&mut heads, &sim.0);
+        sim.0.step(&directions);
+        // ticks with every snake still change nothing and are left out
+        if let Some(replay) = recording.0.as_mut() {
+            if directions.iter().any(|direction| *direction != IVec2::ZERO) {
+                replay.push(&directions);
+            }
         }
-        head_pos.0.x += head.direction.x;
-        head_pos.0.y += head.direction.y;
-
-        // set position of segment to previous position and direction of segment
-        if head.direction != IVec2::ZERO {
-            positions
-                .iter()
-                .zip(segments.0.iter().skip(1))
-                .for_each(|(prev, curr_seg)| {
-                    if let Ok(mut pos) = bodies.get_mut(*curr_seg) {
-                        pos.0 = prev.0;
+    }
+
+    let mut player_died = false;
+    for (head, _, mut segments) in heads.iter_mut() {
+        let snake = sim.0.snake(head.index);
+        match snake.last_outcome() {
+            StepOutcome::Died(_) => {
+                if head.index < game_mode.players() {
+                    player_died = true;
+                }
+                else {
+                    // a dead bot leaves the board, the game goes on
+                    for seg in segments.0.drain(..) {
+                        commands.entity(seg).despawn_recursive();
                     }
-                });
+                }
+                continue;
+            },
+            StepOutcome::Idle => continue,
+            StepOutcome::Moved | StepOutcome::Ate(_) => ()
         }
+
+        // segments a shrinking fruit took off the tail
+        let len = snake.len();
+        if segments.0.len() > len {
+            for seg in segments.0.drain(len..) {
+                commands.entity(seg).despawn_recursive();
+            }
+        }
+
+        // mirror the simulated body onto the segments,
+        // the extra cell of a growing snake is spawned by growth
+        segments
+            .0
+            .iter()
+            .zip(snake.body().iter())
+            .for_each(|(seg, cell)| {
+                if let Ok((mut pos, _)) = bodies.get_mut(*seg) {
+                    pos.0 = *cell;
+                }
+            });
+    }
+    if player_died {
+        gamover_evw.send(GameOverEvent);
     }
 }
 
 pub fn eat(
     mut commands: Commands,
-    heads: Query<&Position, With<SnakeHead>>,
-    fruits: Query<(Entity, &Position), With<Fruit>>,
+    fruits: Query<Entity, With<Fruit>>,
+    sim: Res<Simulation>,
     mut score_changed_evw: EventWriter<ScoreChangedEvent>,
     mut spawn_fruit_evw: EventWriter<SpawnFruitEvent>,
-    mut growth_evw: EventWriter<GrowthEvent>
+    mut growth_evw: EventWriter<GrowthEvent>,
+    mut shrink_evw: EventWriter<ShrinkEvent>,
+    mut speed_boost: ResMut<SpeedBoost>
 ) {
-    let head_pos = heads.single();
-    for (fruit_id, fruit_pos) in fruits.iter() {
-        if fruit_pos.0 == head_pos.0 {
-            // add scores
-            score_changed_evw.send(ScoreChangedEvent);
-            // despawn entity
-            commands.entity(fruit_id).despawn_recursive();
-            // re-spawn fruit
-            spawn_fruit_evw.send(SpawnFruitEvent);
-            // growth snake
+    // two snakes can't eat the same fruit, a head-on crash kills both
+    let Some(kind) = sim.0.snakes()
+        .iter()
+        .find_map(|snake| match snake.last_outcome() {
+            StepOutcome::Ate(kind) => Some(kind),
+            _ => None
+        }) else {
+        return;
+    };
+    // add scores
+    score_changed_evw.send(ScoreChangedEvent);
+    // despawn entity
+    for fruit_id in fruits.iter() {
+        commands.entity(fruit_id).despawn_recursive();
+    }
+    // re-spawn fruit
+    spawn_fruit_evw.send(SpawnFruitEvent);
+    match kind {
+        FruitKind::Shrinking => shrink_evw.send(ShrinkEvent),
+        FruitKind::Speed => {
+            speed_boost.start();
             growth_evw.send(GrowthEvent);
-            break;
-        }
+        },
+        // growth snake
+        _ => growth_evw.send(GrowthEvent)
     }
 }
 
 pub fn random_fruit(
     mut commands: Commands,
-    positions: Query<&Position, With<SnakeSegment>>,
+    mut sim: ResMut<Simulation>,
+    mut rng: ResMut<GameRng>,
     game_assets: Res<GameAssets>,
     game_settings: Res<GameSetting>
 ) {
-    spawn_fruit(&mut commands, positions, &game_assets, &game_settings);
+    spawn_fruit(&mut commands, &mut sim.0, &mut rng, &game_assets, &game_settings);
 }
 
 pub fn growth(
     mut commands: Commands,
-    snake_q: Query<&Position>,
     mut handle_q: Query<&mut Handle<Image>>,
-    mut segments: ResMut<SnakeSegments>,
+    mut heads: Query<(&SnakeHead, &mut SnakeSegments)>,
+    sim: Res<Simulation>,
     game_assets: Res<GameAssets>,
     game_settings: Res<GameSetting>,
 ) {
-    // change tail to body
-    let tail_id = segments.0.last().unwrap();
-    let prev_tail_id = segments.0.iter().nth(segments.0.len()-2).unwrap();
-    let prev_tail = snake_q.get(*prev_tail_id).unwrap();
-    let tail = snake_q.get(*tail_id).unwrap(); 
-    let (dx, dy) = (prev_tail.0 - tail.0).into();
-            // new tail image
-            let texture = match (dx, dy) {
-                (-1, 0) => game_assets.tail_right.clone(),
-                (0, -1) => game_assets.tail_up.clone(),
-                (1, 0) => game_assets.tail_left.clone(),
-                (0, 1) => game_assets.tail_down.clone(),
-                _ => game_assets.tail_up.clone()
-            };
-            // tail becomes body and change its image
-            let mut tail_handle = handle_q.get_mut(*tail_id).unwrap();
-            match (dx, dy) {
-                (-1, 0) | (1, 0) => *tail_handle = game_assets.body_horizontal.clone(),
-                (0, -1) | (0, 1) => *tail_handle = game_assets.body_vertical.clone(),
-                _ => ()
-            }
-            let (nx, ny, nz) = match (dx, dy) {
-                (-1, 0) => (tail.0.x + 1, tail.0.y, 1),
-                (0, -1) => (tail.0.x, tail.0.y + 1, 1),
-                (1, 0) => (tail.0.x - 1, tail.0.y, 1),
-                (0, 1) => (tail.0.x, tail.0.y - 1, 1),
-                // set position outside the board
-                _ => (BOARD_COLS as i32, BOARD_ROWS as i32, 0)
-            };
+    for (head, mut segments) in heads.iter_mut() {
+        grow_snake(&mut commands, &mut handle_q, head.index, &mut segments, &sim.0,
+            &game_assets, &game_settings);
+    }
+}
 
-            segments.0.push(
-                commands.spawn((
-                        SpriteBundle {
-                            texture,
-                            transform: Transform::from_translation(
-                                to_game_xyz(nx, ny, nz, game_settings.snake_width)
-                            )
-                            .with_scale(Vec3::splat(game_settings.game_scale)),
+fn grow_snake(
+    commands: &mut Commands,
+    handle_q: &mut Query<&mut Handle<Image>>,
+    index: usize,
+    segments: &mut SnakeSegments,
+    sim: &SnakeSim,
+    game_assets: &GameAssets,
+    game_settings: &GameSetting,
+) {
+    let body = sim.snake(index).body();
+    let board = sim.board();
+    let old_len = segments.0.len();
+    if body.len() <= old_len {
+        return;
+    }
+    // old tail becomes body and change its image
+    let old_tail = old_len - 1;
+    if let Ok(mut tail_handle) = handle_q.get_mut(segments.0[old_tail]) {
+        if let Some(texture) = body_texture(
+            body[old_tail - 1], body[old_tail], body[old_tail + 1],
+            board, game_assets) {
+            *tail_handle = texture;
+        }
+    }
+
+    for i in old_len..body.len() {
+        // new tail image
+        let texture = if i == body.len() - 1 {
+            tail_texture(body[i-1], body[i], board, game_assets)
+        }
+        else {
+            body_texture(body[i-1], body[i], body[i+1], board, game_assets)
+        };
+        segments.0.push(
+            commands.spawn((
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: snake_tint(index),
                             ..default()
                         },
-                        OnGameScreen,
-                        SnakeSegment,
-                        Position(IVec2::new(nx, ny)),
-                    ))
-                    .id(),
-            );
+                        texture: texture.unwrap_or_default(),
+                        transform: Transform::from_translation(
+                            to_game_xyz(body[i].x, body[i].y, 1,
+                                game_settings)
+                        )
+                        .with_scale(Vec3::splat(game_settings.game_scale)),
+                        ..default()
+                    },
+                    OnGameScreen,
+                    SnakeSegment,
+                    Position(body[i]),
+                    // the new tail holds still on the growth frame
+                    LastPosition(body[i]),
+                ))
+                .id(),
+        );
+    }
 }
 
 pub fn score_change(
-    mut score_q: Query<(&mut Text, &mut Score), With<Score>>
+    mut score_q: Query<(&mut Text, &Score)>,
+    sim: Res<Simulation>,
+    game_mode: Res<GameMode>,
+    locale: Res<Locale>
 ) {
-    if let Ok((mut text, mut score)) = score_q.get_single_mut() {
-        score.0 += 1;
-        text.sections[0].value = "分數: ".to_string() + &score.0.to_string();
-        // println!("text: {:?} score: {:?}", text.sections[0].value, score.0);
+    for (mut text, Score(index)) in score_q.iter_mut() {
+        text.sections[0].value =
+            score_label(*index, sim.0.snake(*index).score(), *game_mode, &locale);
+    }
+}
+
+/// the score on the play bar, per player in a versus game
+pub fn score_label(index: usize, score: u32, game_mode: GameMode, locale: &Locale) -> String {
+    match game_mode {
+        GameMode::Solo => locale.format("score", &[("score", &score)]),
+        GameMode::Versus => locale.format("player_score", &[
+            ("player", &(index + 1)),
+            ("score", &score),
+        ]),
     }
 }
 
 pub fn game_over(
+    mut commands: Commands,
     mut snake_query: Query<&mut SnakeHead>,
-    mut game_state: ResMut<NextState<GameState>>
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut high_scores: ResMut<HighScores>,
+    sim: Res<Simulation>,
+    play_time: Res<PlayTime>,
+    game_settings: Res<GameSetting>,
+    game_mode: Res<GameMode>,
+    demo: Res<Demo>,
+    playback: Option<Res<Playback>>,
+    mut recording: ResMut<Recording>,
+    mut replays: ResMut<Replays>
 ) {
     println!("Game Over");
-    if let Ok(mut head) = snake_query.get_single_mut() {
+    // a playback stays on the last tick until it is left
+    if playback.is_some() {
+        return;
+    }
+    for mut head in snake_query.iter_mut() {
         head.direction = IVec2::ZERO;
     }
 
+    // the demo starts over without keeping a score
+    if demo.playing {
+        game_state.set(GameState::New);
+        return;
+    }
+    finish_recording(&mut recording, &mut replays, &sim);
+
+    // a versus round has a winner instead of a high score
+    if *game_mode == GameMode::Versus {
+        let players = &sim.0.snakes()[..game_mode.players()];
+        let alive: Vec<usize> = (0..players.len())
+            .filter(|i| players[*i].is_alive())
+            .collect();
+        commands.insert_resource(RoundResult {
+            winner: if alive.len() == 1 { Some(alive[0]) } else { None },
+            scores: players.iter().map(|snake| snake.score()).collect(),
+        });
+        game_state.set(GameState::Over);
+        menu_state.set(MenuState::RoundOver);
+        return;
+    }
+
+    let snake = sim.0.snake(0);
+    let entry = HighScoreEntry {
+        score: snake.score(),
+        length: snake.len() as u32,
+        duration: play_time.0.as_secs_f32(),
+        difficulty: game_settings.difficulty,
+        date: unix_now(),
+    };
+    let mut rank = None;
+    if entry.score > 0 {
+        rank = high_scores.insert(entry.clone());
+        if rank.is_some() {
+            high_scores.save();
+        }
+    }
+    let cause = match snake.last_outcome() {
+        StepOutcome::Died(cause) => cause,
+        _ => DeathCause::Wall
+    };
+    commands.insert_resource(LastGame { entry, cause, rank });
+
     game_state.set(GameState::Over);
+    menu_state.set(MenuState::GameOver);
 }
 
-pub fn pause_game(
+/// keep the game just ended with the replays
+fn finish_recording(recording: &mut Recording, replays: &mut Replays, sim: &Simulation) {
+    let Some(mut replay) = recording.0.take() else {
+        return;
+    };
+    if replay.is_empty() {
+        return;
+    }
+    replay.score = sim.0.snake(0).score();
+    replays.add(replay);
+    replays.save();
+}
+
+/// show the progress toward the goal of a campaign stage and end the
+/// stage once it is reached
+pub fn check_goal(
+    mut goal_q: Query<&mut Text, With<GoalText>>,
     mut snake_query: Query<&mut SnakeHead>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut progress: ResMut<CampaignProgress>,
+    mut level_complete_evw: EventWriter<LevelCompleteEvent>,
+    campaign: Res<Campaign>,
+    sim: Res<Simulation>,
+    play_time: Res<PlayTime>,
+    playback: Option<Res<Playback>>,
+    mut recording: ResMut<Recording>,
+    mut replays: ResMut<Replays>,
+    locale: Res<Locale>
+) {
+    let (Some(stage), Some(goal)) = (campaign.stage, campaign.goal) else {
+        return;
+    };
+    let snake = sim.0.snake(0);
+    let (done, target) = goal.progress(snake, play_time.0);
+    if let Ok(mut text) = goal_q.get_single_mut() {
+        let label = locale.format("stage_progress", &[
+            ("stage", &(stage + 1)),
+            ("done", &done),
+            ("target", &target),
+        ]);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+    // a dying snake is handled by game_over, a playback plays on to its end
+    if !snake.is_alive() || !goal.is_reached(snake, play_time.0) || playback.is_some() {
+        return;
+    }
+
+    info!("stage {} cleared", stage + 1);
+    for mut head in snake_query.iter_mut() {
+        head.direction = IVec2::ZERO;
+    }
+    finish_recording(&mut recording, &mut replays, &sim);
+    progress.clear(stage);
+    progress.save();
+    level_complete_evw.send(LevelCompleteEvent);
+    game_state.set(GameState::Over);
+    menu_state.set(MenuState::LevelComplete);
+}
+
+/// back on the main menu, the next game is an endless solo one unless the
+/// campaign or versus is picked again, and it is played with the player's
+/// own settings
+pub fn reset_game_mode(
+    mut campaign: ResMut<Campaign>,
+    mut game_mode: ResMut<GameMode>,
+    mut game_settings: ResMut<GameSetting>
+) {
+    *campaign = Campaign::default();
+    *game_mode = GameMode::default();
+    game_settings.restore();
+}
+
+pub fn pause_game(
+    mut snake_query: Query<(&mut SnakeHead, &mut InputQueue)>,
     mut menu_q: Query<(&mut Visibility, &OnPlayMenuScreen)>,
 ) {
     // println!("game is paused");
     
     // freeze all segments
-    if let Ok(mut head) = snake_query.get_single_mut() {
+    for (mut head, mut queue) in snake_query.iter_mut() {
         head.direction = IVec2::ZERO;
+        queue.0.clear();
     }
 
     if let Ok((mut menu_visibility, _)) = menu_q.get_single_mut() {
@@ -462,3 +1037,25 @@ pub fn unpause_game(
         *menu_visibility = Visibility::Visible;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_paused_game_is_in_game_behind_its_settings() {
+        for menu_state in [MenuState::Pause, MenuState::Settings, MenuState::Controls] {
+            assert!(in_game(GameState::Menu, menu_state, MenuState::Pause));
+        }
+        assert!(in_game(GameState::Play, MenuState::Play, MenuState::Main));
+    }
+
+    #[test]
+    fn the_menus_outside_a_game_are_not_in_game() {
+        for menu_state in [MenuState::Main, MenuState::Settings, MenuState::Controls] {
+            assert!(!in_game(GameState::Menu, menu_state, MenuState::Main));
+        }
+        assert!(!in_game(GameState::Over, MenuState::GameOver, MenuState::Pause));
+        assert!(!in_game(GameState::Menu, MenuState::HighScores, MenuState::Pause));
+    }
+}
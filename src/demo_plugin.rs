@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+use crate::{
+    GameState,
+    MenuState,
+    components::OnMainMenuScreen,
+    resources::Demo,
+    game_plugin::Phase,
+};
+
+pub struct DemoPlugin;
+
+impl Plugin for DemoPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(MenuState::Main), reset_idle)
+            .add_systems(Update, (
+                start_demo.run_if(in_state(MenuState::Main)
+                    .and_then(in_state(GameState::Menu))),
+                stop_demo
+                    .before(Phase::Input)
+                    .run_if(|demo: Res<Demo>| demo.playing),
+            ))
+            .init_resource::<Demo>();
+    }
+}
+
+/// true if a key, a mouse button, a finger or a gamepad button went down
+/// this frame
+fn any_input(
+    kb_input: &Input<KeyCode>,
+    mouse_input: &Input<MouseButton>,
+    touches: &Touches,
+    buttons: &Input<GamepadButton>
+) -> bool {
+    kb_input.get_just_pressed().next().is_some() ||
+        mouse_input.get_just_pressed().next().is_some() ||
+        touches.any_just_pressed() ||
+        buttons.get_just_pressed().next().is_some()
+}
+
+fn reset_idle(mut demo: ResMut<Demo>) {
+    demo.stop();
+}
+
+/// let a bot play behind a main menu left alone for a while
+fn start_demo(
+    time: Res<Time>,
+    kb_input: Res<Input<KeyCode>>,
+    mouse_input: Res<Input<MouseButton>>,
+    touches: Res<Touches>,
+    buttons: Res<Input<GamepadButton>>,
+    mut cursor_evr: EventReader<CursorMoved>,
+    mut demo: ResMut<Demo>,
+    mut menu_q: Query<&mut Visibility, With<OnMainMenuScreen>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    let moved = cursor_evr.read().count() > 0;
+    if moved || any_input(&kb_input, &mouse_input, &touches, &buttons) {
+        demo.idle.reset();
+        return;
+    }
+    if !demo.idle.tick(time.delta()).just_finished() {
+        return;
+    }
+    info!("demo started");
+    demo.playing = true;
+    for mut visibility in menu_q.iter_mut() {
+        *visibility = Visibility::Hidden;
+    }
+    game_state.set(GameState::New);
+}
+
+/// any input brings the menu back, it is used up so the menu doesn't act
+/// on it as well
+fn stop_demo(
+    mut kb_input: ResMut<Input<KeyCode>>,
+    mut mouse_input: ResMut<Input<MouseButton>>,
+    touches: Res<Touches>,
+    mut buttons: ResMut<Input<GamepadButton>>,
+    mut demo: ResMut<Demo>,
+    mut menu_q: Query<&mut Visibility, With<OnMainMenuScreen>>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if !any_input(&kb_input, &mouse_input, &touches, &buttons) {
+        return;
+    }
+    info!("demo stopped");
+    kb_input.clear();
+    mouse_input.clear();
+    buttons.clear();
+    demo.stop();
+    for mut visibility in menu_q.iter_mut() {
+        *visibility = Visibility::Visible;
+    }
+    game_state.set(GameState::Menu);
+}
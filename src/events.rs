@@ -1,4 +1,5 @@
 use bevy::ecs::event::Event;
+use crate::components::MenuButtonAction;
 
 #[derive(Event)]
 pub struct GameOverEvent;
@@ -6,6 +7,10 @@ pub struct GameOverEvent;
 #[derive(Event)]
 pub struct GrowthEvent;
 
+/// a shrinking fruit took segments off the tail
+#[derive(Event)]
+pub struct ShrinkEvent;
+
 #[derive(Event)]
 pub struct SpawnFruitEvent;
 
@@ -14,3 +19,16 @@ pub struct CollisionEvent;
 
 #[derive(Event)]
 pub struct ScoreChangedEvent;
+
+/// the snakes turned with the board or changed their looks, their sprites
+/// are placed and picked again out of turn
+#[derive(Event)]
+pub struct RedrawSnakesEvent;
+
+/// the goal of a campaign stage was reached
+#[derive(Event)]
+pub struct LevelCompleteEvent;
+
+/// a menu button was clicked, touched or confirmed by keys
+#[derive(Event)]
+pub struct MenuActionEvent(pub MenuButtonAction);
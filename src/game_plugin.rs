@@ -1,14 +1,16 @@
-use std::time::Duration;
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::prelude::*;
+use bevy::app::AppExit;
 use crate::{
     control::*,
     GameState, 
-    resources::SnakeSegments,
+    MenuState,
+    resources::{Simulation, TickTimer, PlayTime, Campaign, SpeedBoost, GameMode, GameRng},
     events::*,
     input::*,
     graphics::*, 
-    prelude::*,
-    menu_plugin::play_menu_setup
+    menu_plugin::play_menu_setup,
+    level::{Level, LevelLoader},
+    replay::{Recording, Playback}
 };
 
 #[derive(SystemSet, Hash, PartialEq, Eq, Clone, Debug)]
@@ -27,40 +29,101 @@ impl Plugin for GamePlugin {
                     play_menu_setup,
                     init_snake,
                     init_fruit, 
+                    init_tick,
                     init_wall
                 ).chain()
             )
+            .add_systems(OnEnter(GameState::Continue), 
+                (
+                    continue_game,
+                    play_menu_setup,
+                    continue_snakes,
+                    init_fruit,
+                    init_wall,
+                    // the score bar shows the saved scores
+                    apply_deferred,
+                    score_change
+                ).chain()
+            )
             .add_systems(OnEnter(GameState::Resume), unpause_game)
             .add_systems(OnEnter(GameState::Menu), pause_game)
+            .add_systems(OnEnter(MenuState::Main), reset_game_mode)
             .add_systems(Update, (
                 pause
                     .in_set(Phase::Input)
-                    .run_if(not(in_state(GameState::Menu))),
+                    .run_if(in_state(GameState::Play)
+                        .and_then(not(resource_exists::<Playback>()))),
                 (
-                    keyboard.in_set(Phase::Input),
-                    // touch_events.in_set(Phase::Input),
-                    touch_input.in_set(Phase::Input),
+                    // a playback takes every move from its replay
+                    (
+                        keyboard,
+                        gamepad,
+                        // touch_events,
+                        touch_input,
+                    )
+                        .in_set(Phase::Input)
+                        .run_if(not(resource_exists::<Playback>())),
+                    tick_timer.before(move_snake),
+                    bots
+                        .after(tick_timer)
+                        .before(move_snake)
+                        .run_if(on_tick.and_then(not(resource_exists::<Playback>()))),
+                    play_time,
+                    speed_boost.before(speed_up),
                     (
-                        move_snake.after(Phase::Input),
+                        // a board turning between two ticks of a playback
+                        // turns before the next one
+                        move_snake.after(Phase::Input).after(orient),
                         eat,
-                        snake_transform,
+                        expire_fruit,
                         fruit_transform,
                     )
                     .chain()
-                    .run_if(on_timer(Duration::from_millis(GAME_FPS))),
+                    .run_if(on_tick),
+                    // between the ticks, once they moved the segments
+                    interpolate_snake
+                        .after(snake_transform)
+                        .run_if(smooth_movement),
                     growth.run_if(on_event::<GrowthEvent>()),
-                    random_fruit.run_if(on_event::<SpawnFruitEvent>()),
+                    speed_up.after(eat),
+                    // a new fruit is always down before the next move
+                    random_fruit
+                        .before(move_snake)
+                        .run_if(on_event::<SpawnFruitEvent>()),
                     game_over.run_if(on_event::<GameOverEvent>()),
-                    score_change.run_if(on_event::<ScoreChangedEvent>())
+                    score_change.run_if(on_event::<ScoreChangedEvent>()),
+                    check_goal.after(play_time).after(move_snake)
                 )
-                .run_if(in_state(GameState::Play))
+                .run_if(in_state(GameState::Play)),
+                // every tick, and out of turn when the board turns or the
+                // theme changes, a menu may be open then
+                snake_transform
+                    .after(expire_fruit)
+                    .after(orient)
+                    .after(apply_theme)
+                    .run_if(on_event::<RedrawSnakesEvent>()
+                        .or_else(in_state(GameState::Play).and_then(on_tick))),
             ))
+            // the quit button and a closed window both end in an AppExit
+            .add_systems(Last, save_game.run_if(on_event::<AppExit>()))
+            .init_asset::<Level>()
+            .init_asset_loader::<LevelLoader>()
             .add_event::<GameOverEvent>()
             .add_event::<SpawnFruitEvent>()
             .add_event::<GrowthEvent>()
+            .add_event::<ShrinkEvent>()
             .add_event::<CollisionEvent>()
             .add_event::<ScoreChangedEvent>()
+            .add_event::<LevelCompleteEvent>()
+            .add_event::<RedrawSnakesEvent>()
             //.insert_resource(TouchPosition(Vec2::default()))
-            .insert_resource(SnakeSegments::default());
+            .insert_resource(Simulation::default())
+            .insert_resource(TickTimer::default())
+            .insert_resource(PlayTime::default())
+            .insert_resource(SpeedBoost::default())
+            .insert_resource(Campaign::default())
+            .insert_resource(GameMode::default())
+            .insert_resource(GameRng::default())
+            .insert_resource(Recording::default());
     }
 }
@@ -1,85 +1,183 @@
 use bevy::{
     prelude::*, 
-    winit::WinitWindows,
-    window::{PrimaryWindow, WindowResized}
+    window::{PrimaryWindow, WindowResized, WindowMode}
 };
 
 use crate::{
     GameAssets,
-    components::{Position, SnakeSegment, Fruit, SnakeHead, MainCamera}, 
-    resources::{SnakeSegments, GameSetting},
+    components::{
+        Position, LastPosition, SnakeSegment, SnakeHead, SnakeSegments, Fruit, Scenery,
+        InputQueue, ToSelectOption
+    }, 
+    resources::{GameSetting, Simulation, TickTimer, Layout},
+    control::{draw_wall, draw_obstacles},
+    sim::{Board, FruitKind, SnakeSim, turn_direction},
+    replay::{Recording, Playback},
+    theme::{Palette, Theme, ThemeRole},
+    events::RedrawSnakesEvent,
     prelude::*,
 };
 
-#[cfg(target_arch = "wasm32")]
-use bevy::window::WindowCreated;
-
 pub fn snake_transform(
     mut transforms: Query<(&Position, &mut Transform)>,
-    heads: Query<&SnakeHead>,
     mut handles: Query<&mut Handle<Image>, With<SnakeSegment>>,
-    segments: ResMut<SnakeSegments>,
+    heads: Query<(&SnakeHead, &SnakeSegments)>,
+    sim: Res<Simulation>,
     game_assets: Res<GameAssets>,
     game_settings: Res<GameSetting>
 ) {
     for (pos, mut transform) in transforms.iter_mut() {
         transform.translation = to_game_xyz(pos.0.x, pos.0.y, 1,
-            game_settings.snake_width);
-    }
-    // head direction
-    let head = heads.single();
-    if let Ok(mut handle) = handles.get_mut(*segments.0.first().unwrap()) {
-        match (head.direction.x as i32, head.direction.y as i32) {
-            (1, 0) => *handle = game_assets.head_right.clone(),
-            (0, 1) => *handle = game_assets.head_up.clone(),
-            (-1, 0) => *handle = game_assets.head_left.clone(),
-            (0, -1) => *handle = game_assets.head_down.clone(),
-            _ => ()
-        }
+            &game_settings);
     }
-    // tail direction
-    let tail_id = segments.0.last().unwrap();
-    let prev_tail_id = segments.0.iter().nth(segments.0.len()-2).unwrap();
-    if let Ok((pos_t, _)) = transforms.get(*tail_id) {
-        if let Ok((pos_p, _)) = transforms.get(*prev_tail_id) {
-            let (dx, dy) = (pos_p.0 - pos_t.0).into();
-            if let Ok(mut handle_t) = handles.get_mut(*tail_id) {
-                match (dx, dy) {
-                    (-1, 0) => *handle_t = game_assets.tail_right.clone(),
-                    (0, -1) => *handle_t = game_assets.tail_up.clone(),
-                    (1, 0) => *handle_t = game_assets.tail_left.clone(),
-                    (0, 1) => *handle_t = game_assets.tail_down.clone(),
-                    _ => ()
+    let board = sim.0.board();
+    for (head, segments) in heads.iter() {
+        let segments = &segments.0;
+        // a dead bot leaves its head without segments
+        if segments.len() < 2 {
+            continue;
+        }
+        // head direction
+        if let Ok(mut handle) = handles.get_mut(*segments.first().unwrap()) {
+            if let Some(texture) = head_texture(sim.0.heading(head.index), &game_assets) {
+                *handle = texture;
+            }
+        }
+        // tail direction
+        let tail_id = segments.last().unwrap();
+        let prev_tail_id = &segments[segments.len()-2];
+        if let Ok((pos_t, _)) = transforms.get(*tail_id) {
+            if let Ok((pos_p, _)) = transforms.get(*prev_tail_id) {
+                if let Ok(mut handle_t) = handles.get_mut(*tail_id) {
+                    if let Some(texture) = tail_texture(pos_p.0, pos_t.0, board, &game_assets) {
+                        *handle_t = texture;
+                    }
                 }
             }
         }
-    }
-    // body direction
-    for i in 1..(segments.0.len()-1) {
-        let prev = transforms.get(segments.0[i-1]).unwrap(); 
-        let curr = transforms.get(segments.0[i]).unwrap();
-        let next = transforms.get(segments.0[i+1]).unwrap();
-        let mut curr_handle = handles.get_mut(segments.0[i]).unwrap();
-        let (pc_x, pc_y) = (prev.0.0 - curr.0.0).into();
-        let (cn_x, cn_y) = (curr.0.0 - next.0.0).into();
-        match (pc_x, pc_y, cn_x, cn_y) {
-            (-1, 0, 0, 1) => *curr_handle = game_assets.body_bottomleft.clone(),
-            (0, -1, 1, 0) => *curr_handle = game_assets.body_bottomleft.clone(),
-            (1, 0, 0, 1) => *curr_handle = game_assets.body_bottomright.clone(),
-            (0, -1, -1, 0) => *curr_handle = game_assets.body_bottomright.clone(),
-            (-1, 0,-1, 0) => *curr_handle = game_assets.body_horizontal.clone(),
-            (1, 0, 1, 0) => *curr_handle = game_assets.body_horizontal.clone(),
-            (0, 1, 1, 0) => *curr_handle = game_assets.body_topleft.clone(),
-            (-1, 0, 0, -1) => *curr_handle = game_assets.body_topleft.clone(),
-            (0, 1, -1, 0) => *curr_handle = game_assets.body_topright.clone(),
-            (1, 0, 0, -1) => *curr_handle = game_assets.body_topright.clone(),
-            (0, 1, 0, 1) => *curr_handle= game_assets.body_vertical.clone(),
-            (0, -1, 0, -1) => *curr_handle = game_assets.body_vertical.clone(),
-            _ => ()
+        // body direction
+        for i in 1..(segments.len()-1) {
+            let prev = transforms.get(segments[i-1]).unwrap().0.0;
+            let curr = transforms.get(segments[i]).unwrap().0.0;
+            let next = transforms.get(segments[i+1]).unwrap().0.0;
+            let mut curr_handle = handles.get_mut(segments[i]).unwrap();
+            if let Some(texture) = body_texture(prev, curr, next, board, &game_assets) {
+                *curr_handle = texture;
+            }
         }
     }
 }
 
+/// slide the snake segments from their last cell to their cell as the tick
+/// runs out, the sim still moves them a whole cell per tick
+pub fn interpolate_snake(
+    mut segments: Query<(&Position, &LastPosition, &mut Transform), With<SnakeSegment>>,
+    timer: Res<TickTimer>,
+    sim: Res<Simulation>,
+    game_settings: Res<GameSetting>
+) {
+    let progress = timer.0.percent();
+    let board = sim.0.board();
+    for (pos, last, mut transform) in segments.iter_mut() {
+        // every segment steps into a neighbouring cell, so the body follows
+        // the head around corners, and through a portal it comes in from
+        // beyond the wall
+        let from = pos.0 - board.offset(last.0, pos.0);
+        transform.translation = to_game_xyz(from.x, from.y, 1, &game_settings)
+            .lerp(to_game_xyz(pos.0.x, pos.0.y, 1, &game_settings), progress);
+    }
+}
+
+/// run condition of the smooth movement
+pub fn smooth_movement(game_settings: Res<GameSetting>) -> bool {
+    game_settings.smooth
+}
+
+/// colour multiplied into the sprites of snake `index`, the first snake
+/// keeps its images as they are
+pub fn snake_tint(index: usize) -> Color {
+    const TINTS: [Color; 4] = [
+        Color::rgb(0.55, 0.7, 1.0),
+        Color::rgb(1.0, 0.55, 0.55),
+        Color::rgb(0.8, 0.6, 1.0),
+        Color::rgb(1.0, 0.85, 0.4),
+    ];
+    match index {
+        0 => Color::WHITE,
+        _ => TINTS[(index - 1) % TINTS.len()],
+    }
+}
+
+/// head image facing `direction`
+pub fn head_texture(
+    direction: IVec2,
+    game_assets: &GameAssets
+) -> Option<Handle<Image>> {
+    match (direction.x, direction.y) {
+        (1, 0) => Some(game_assets.head_right.clone()),
+        (0, 1) => Some(game_assets.head_up.clone()),
+        (-1, 0) => Some(game_assets.head_left.clone()),
+        (0, -1) => Some(game_assets.head_down.clone()),
+        _ => None
+    }
+}
+
+/// tail image pointing away from the segment in front of it,
+/// `board` resolves neighbours across a portal wall
+pub fn tail_texture(
+    prev: IVec2,
+    tail: IVec2,
+    board: Board,
+    game_assets: &GameAssets
+) -> Option<Handle<Image>> {
+    let (dx, dy) = board.offset(tail, prev).into();
+    match (dx, dy) {
+        (-1, 0) => Some(game_assets.tail_right.clone()),
+        (0, -1) => Some(game_assets.tail_up.clone()),
+        (1, 0) => Some(game_assets.tail_left.clone()),
+        (0, 1) => Some(game_assets.tail_down.clone()),
+        _ => None
+    }
+}
+
+/// body image joining the segments before and after `curr`
+pub fn body_texture(
+    prev: IVec2,
+    curr: IVec2,
+    next: IVec2,
+    board: Board,
+    game_assets: &GameAssets
+) -> Option<Handle<Image>> {
+    let (pc_x, pc_y) = board.offset(curr, prev).into();
+    let (cn_x, cn_y) = board.offset(next, curr).into();
+    match (pc_x, pc_y, cn_x, cn_y) {
+        (-1, 0, 0, 1) => Some(game_assets.body_bottomleft.clone()),
+        (0, -1, 1, 0) => Some(game_assets.body_bottomleft.clone()),
+        (1, 0, 0, 1) => Some(game_assets.body_bottomright.clone()),
+        (0, -1, -1, 0) => Some(game_assets.body_bottomright.clone()),
+        (-1, 0,-1, 0) => Some(game_assets.body_horizontal.clone()),
+        (1, 0, 1, 0) => Some(game_assets.body_horizontal.clone()),
+        (0, 1, 1, 0) => Some(game_assets.body_topleft.clone()),
+        (-1, 0, 0, -1) => Some(game_assets.body_topleft.clone()),
+        (0, 1, -1, 0) => Some(game_assets.body_topright.clone()),
+        (1, 0, 0, -1) => Some(game_assets.body_topright.clone()),
+        (0, 1, 0, 1) => Some(game_assets.body_vertical.clone()),
+        (0, -1, 0, -1) => Some(game_assets.body_vertical.clone()),
+        _ => None
+    }
+}
+
+/// sprite of a fruit of `kind`
+pub fn fruit_texture(kind: FruitKind, game_assets: &GameAssets) -> Handle<Image> {
+    match kind {
+        FruitKind::Apple => game_assets.apple.clone(),
+        FruitKind::Golden => game_assets.golden_fruit.clone(),
+        FruitKind::Shrinking => game_assets.shrink_fruit.clone(),
+        FruitKind::Speed => game_assets.speed_fruit.clone(),
+        FruitKind::Bonus => game_assets.bonus_fruit.clone(),
+    }
+}
+
 pub fn fruit_transform(
     mut transforms: Query<(&Position, &mut Transform), With<Fruit>>,
     game_settings: Res<GameSetting>
@@ -89,100 +187,280 @@ pub fn fruit_transform(
             fruit_pos.0.x, 
             fruit_pos.0.y,
             1,
-            game_settings.snake_width
+            &game_settings
         );
     }
 }
 
-pub fn to_game_xyz(x: i32, y: i32, z: i32, snake_width: f32) -> Vec3 {
+pub fn to_game_xyz(x: i32, y: i32, z: i32, game_settings: &GameSetting) -> Vec3 {
+    let center = game_settings.board.center();
+    let offset = game_settings.board_offset();
     Vec3::new(
-        x as f32 * snake_width,
-        y as f32 * snake_width - BOARD_OFFSET_Y/2.,
+        (x as f32 - center.x) * game_settings.snake_width + offset.x,
+        (y as f32 - center.y) * game_settings.snake_width + offset.y,
         z as f32
     )
 }
 
-#[cfg(target_arch = "wasm32")]
-pub fn setup_ui(
-    mut window_q: Query<(Entity, &mut Window), With<PrimaryWindow>>,
-    mut game_settings: ResMut<GameSetting>,
-    mut window_created_evr: EventReader<WindowCreated>
-    // winit_window: NonSend<WinitWindows>, 
+/// dress the board, the snakes, the fruit and the menus on screen in the
+/// theme of the settings, once it is loaded
+pub fn apply_theme(
+    asset_server: Res<AssetServer>,
+    themes: Res<Assets<Theme>>,
+    game_settings: Res<GameSetting>,
+    mut game_assets: ResMut<GameAssets>,
+    mut palette: ResMut<Palette>,
+    mut clear_color: ResMut<ClearColor>,
+    mut themed: Query<(
+        &ThemeRole,
+        Option<&mut BackgroundColor>,
+        Option<&mut Text>,
+        Option<&mut Sprite>,
+        Option<&Interaction>,
+        Option<&ToSelectOption>
+    )>,
+    mut fruits: Query<&mut Handle<Image>, With<Fruit>>,
+    sim: Res<Simulation>,
+    mut redraw_evw: EventWriter<RedrawSnakesEvent>
 ) {
-    for _event in window_created_evr.read() {
-        // info!("window created");
-        let (_primary_window, mut window) = window_q.single_mut();
-        let wasm_window = web_sys::window().unwrap();
-        // info!("\ncanvas width:{:?}, height:{:?}",
-        //     wasm_window.inner_width(), wasm_window.inner_height());
-        let game_width = (BOARD_COLS as f32 * game_settings.snake_width +
-            2. * WALL_WIDTH) as f64;
-        let game_height = (BOARD_ROWS as f32 * game_settings.snake_width +
-            2. * WALL_WIDTH + BOARD_OFFSET_Y) as f64;
-        let width = wasm_window.inner_width().unwrap().as_f64().unwrap();
-        let height = wasm_window.inner_height().unwrap().as_f64().unwrap();
-        // info!("portrait mode and w={:?} gw={:?}", width, game_width);
-        // info!("h={:?} gh={:?}", height, game_height);
-        if width >= height {
-            if height * 0.90 < game_height {
-                let scale = (height * 0.90 / game_height) as f32;
-                game_settings.game_scale = scale;
-                game_settings.snake_width *= scale;
+    let Some(theme) = game_assets.themes
+        .get(game_settings.theme)
+        .and_then(|handle| themes.get(handle)) else {
+        return;
+    };
+    if *palette != theme.palette {
+        *palette = theme.palette;
+        for (role, background, text, sprite, interaction, selected) in themed.iter_mut() {
+            let color = match (role, interaction) {
+                (ThemeRole::Button, Some(interaction)) =>
+                    palette.button_color(*interaction, selected.is_some()),
+                _ => palette.color(*role),
+            };
+            // a panel over the board keeps its see-through alpha
+            if let Some(mut background) = background {
+                background.0 = color.with_a(background.0.a());
             }
-        }
-        else {
-            if width < game_width {
-                let scale = (width / game_width) as f32;
-                game_settings.game_scale = scale;
-                game_settings.snake_width *= scale;
+            if let Some(mut text) = text {
+                for section in text.sections.iter_mut() {
+                    section.style.color = color.with_a(section.style.color.a());
+                }
             }
-            else if height * 0.85 < game_height {
-                let scale = (height * 0.85 / game_height) as f32;
-                game_settings.game_scale = scale;
-                game_settings.snake_width *= scale;
+            if let Some(mut sprite) = sprite {
+                sprite.color = color.with_a(sprite.color.a());
             }
         }
-        let win_width = BOARD_COLS as f32 * game_settings.snake_width +
-            2. * WALL_WIDTH;
-        let win_height = BOARD_ROWS as f32 * game_settings.snake_width +
-            2. * WALL_WIDTH + BOARD_OFFSET_Y;
-        (*window).resolution.set(win_width, win_height);
-    }
-    //info!("\nw={:?}, h={:?}", win_width, win_height);
-    // let winit = winit_window.get_window(primary_window).unwrap();
-    //let monitor = winit.current_monitor().unwrap();
-    // let is = winit.inner_size();
-    // let sf = winit.scale_factor();
-    // let width = is.width as f64 / sf;
-    // let height = is.height as f64 / sf;
-    
-    // info!("\ninner_size={:?}\nscale_factor={:?}", is, sf);
-    // info!("\nwidth={:?}, height={:?}", width, height);
-    // info!("\nwindow_resolution={:?}", window.resolution);
-    // calculate new settings
-}
-
-pub fn _on_size_changed(
-    winit_window: NonSend<WinitWindows>, 
-    mut window_q: Query<Entity, With<PrimaryWindow>>,
-    mut sizechanged_evr: EventReader<WindowResized>,
-    camera_q: Query<&Camera, With<MainCamera>>
+        clear_color.0 = palette.background;
+    }
+    if game_assets.sprites != theme.sprites {
+        info!("sprites from {}", theme.sprites);
+        game_assets.load_sprites(&asset_server, &theme.sprites);
+        for mut handle in fruits.iter_mut() {
+            *handle = fruit_texture(sim.0.fruit_kind(), &game_assets);
+        }
+        // the snakes follow in snake_transform
+        redraw_evw.send(RedrawSnakesEvent);
+    }
+}
+
+/// fit the window to a new board size, a fullscreen window keeps its size
+/// and so does a landscape one, its shape is the player's
+pub fn resize_window(
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>,
+    game_settings: Res<GameSetting>,
+    mut board: Local<Option<Board>>
 ) {
-    if let Ok(camera) = camera_q.get_single() {
-        info!("\ncamera={:?}", camera);
-    }
-
-    for resized in sizechanged_evr.read() {
-        info!("\nresized");
-        let primary_window = window_q.single_mut();
-        let winit = winit_window.get_window(primary_window).unwrap();
-        let is = winit.inner_size();
-        let sf = winit.scale_factor();
-        let width = is.width as f64 / sf;
-        let height = is.height as f64 / sf;
-        
-        info!("\nwin_resized: width={:?}, height={:?}", resized.width, resized.height);
-        info!("\ninner_size={:?}\nscale_factor={:?}", is, sf);
-        info!("\nwidth={:?}, height={:?}", width, height);
+    if *board == Some(game_settings.board) {
+        return;
+    }
+    *board = Some(game_settings.board);
+    if let Ok(mut window) = window_q.get_single_mut() {
+        if window.mode != WindowMode::Windowed ||
+            game_settings.layout == Layout::Landscape {
+            return;
+        }
+        let win_size = game_settings.window_size();
+        window.resolution.set(win_size.x, win_size.y);
+    }
+}
+
+/// F11 switches between a window and the full screen
+pub fn toggle_fullscreen(
+    kb_input: Res<Input<KeyCode>>,
+    mut window_q: Query<&mut Window, With<PrimaryWindow>>
+) {
+    if !kb_input.just_pressed(KeyCode::F11) {
+        return;
+    }
+    if let Ok(mut window) = window_q.get_single_mut() {
+        window.mode = match window.mode {
+            WindowMode::Windowed => WindowMode::BorderlessFullscreen,
+            _ => WindowMode::Windowed,
+        };
+        info!("window mode {:?}", window.mode);
+    }
+}
+
+/// follow the shape of the window, the score bar moves beside the board in
+/// a landscape window and the board turns a quarter if the player wants it
+/// to, the game goes on where it was and its replay turns there too
+pub fn orient(
+    mut commands: Commands,
+    window_q: Query<&Window, With<PrimaryWindow>>,
+    mut game_settings: ResMut<GameSetting>,
+    mut sim: ResMut<Simulation>,
+    mut heads: Query<(&mut SnakeHead, &mut InputQueue)>,
+    mut positions: Query<(&mut Position, Option<&mut LastPosition>)>,
+    scenery: Query<Entity, With<Scenery>>,
+    mut recording: ResMut<Recording>,
+    playback: Option<Res<Playback>>,
+    palette: Res<Palette>,
+    mut redraw_evw: EventWriter<RedrawSnakesEvent>
+) {
+    let Ok(window) = window_q.get_single() else {
+        return;
+    };
+    let layout = Layout::of(Vec2::new(window.width(), window.height()));
+    if game_settings.layout != layout {
+        info!("{layout:?} layout");
+        game_settings.layout = layout;
+    }
+    // a playback turns its board where the recorded game did
+    let turned = match playback {
+        Some(playback) => playback.turned(),
+        None => layout == Layout::Landscape && game_settings.turn_landscape,
+    };
+    if game_settings.turned == turned {
+        return;
+    }
+    // clockwise into landscape, back the other way
+    let clockwise = turned;
+    let board = game_settings.board;
+    game_settings.turned = turned;
+    game_settings.board = board.turned();
+    sim.0 = sim.0.turned(clockwise);
+    for (mut head, mut queue) in heads.iter_mut() {
+        head.direction = turn_direction(head.direction, clockwise);
+        for direction in queue.0.iter_mut() {
+            *direction = turn_direction(*direction, clockwise);
+        }
+    }
+    for (mut pos, last) in positions.iter_mut() {
+        pos.0 = board.turn(pos.0, clockwise);
+        if let Some(mut last) = last {
+            last.0 = board.turn(last.0, clockwise);
+        }
+    }
+    // the moves from here on are on the turned board
+    if let Some(replay) = recording.0.as_mut() {
+        replay.turn();
+    }
+    redraw_scenery(&mut commands, &scenery, &sim.0, &game_settings, &palette);
+    redraw_evw.send(RedrawSnakesEvent);
+}
+
+/// scale the board, the sprites and the menus already on screen to the
+/// new size of the window, the game goes on
+pub fn on_size_changed(
+    mut commands: Commands,
+    mut resized_evr: EventReader<WindowResized>,
+    mut game_settings: ResMut<GameSetting>,
+    mut nodes: Query<&mut Style>,
+    mut texts: Query<&mut Text>,
+    mut icons: Query<&mut Transform, (With<UiImage>, Without<Position>)>,
+    mut sprites: Query<(&Position, &mut Transform), Without<UiImage>>,
+    scenery: Query<Entity, With<Scenery>>,
+    sim: Res<Simulation>,
+    palette: Res<Palette>
+) {
+    let Some(resized) = resized_evr.read().last() else {
+        return;
+    };
+    let mut scale = game_settings.fit_scale(Vec2::new(resized.width, resized.height));
+    let mut ratio = scale / game_settings.game_scale;
+    if (ratio - 1.).abs() < 0.01 {
+        // the board still moves if the layout changed
+        if !game_settings.is_changed() {
+            return;
+        }
+        (scale, ratio) = (game_settings.game_scale, 1.);
+    }
+    info!("window {}x{}, scale {scale:.2}", resized.width, resized.height);
+    game_settings.game_scale = scale;
+    game_settings.snake_width = SNAKE_WIDTH * scale;
+
+    // the menus and the play bar were laid out for the old scale
+    for mut style in nodes.iter_mut() {
+        scale_style(&mut style, ratio);
+    }
+    for mut text in texts.iter_mut() {
+        for section in text.sections.iter_mut() {
+            section.style.font_size *= ratio;
+        }
+    }
+    for mut transform in icons.iter_mut() {
+        transform.scale *= ratio;
+    }
+
+    // snakes and fruit move to their cells on the scaled board
+    for (pos, mut transform) in sprites.iter_mut() {
+        transform.translation = to_game_xyz(pos.0.x, pos.0.y, 1, &game_settings);
+        transform.scale = Vec3::splat(scale);
+    }
+    redraw_scenery(&mut commands, &scenery, &sim.0, &game_settings, &palette);
+}
+
+/// walls, grid and obstacles are drawn again, only while there is a game
+fn redraw_scenery(
+    commands: &mut Commands,
+    scenery: &Query<Entity, With<Scenery>>,
+    sim: &SnakeSim,
+    game_settings: &GameSetting,
+    palette: &Palette
+) {
+    if scenery.is_empty() {
+        return;
+    }
+    for entity in scenery.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    draw_wall(commands, game_settings, palette);
+    draw_obstacles(commands, sim, game_settings, palette);
+}
+
+/// draw the board again when the grid is switched on or off
+pub fn redraw_grid(
+    mut commands: Commands,
+    game_settings: Res<GameSetting>,
+    scenery: Query<Entity, With<Scenery>>,
+    sim: Res<Simulation>,
+    palette: Res<Palette>,
+    mut grid: Local<Option<bool>>
+) {
+    if grid.replace(game_settings.grid).is_none_or(|grid| grid == game_settings.grid) {
+        return;
+    }
+    redraw_scenery(&mut commands, &scenery, &sim.0, &game_settings, &palette);
+}
+
+/// multiply every length in pixels of `style` by `ratio`
+fn scale_style(style: &mut Style, ratio: f32) {
+    let scale = |val: &mut Val| {
+        if let Val::Px(px) = val {
+            *px *= ratio;
+        }
+    };
+    for val in [
+        &mut style.left, &mut style.right, &mut style.top, &mut style.bottom,
+        &mut style.width, &mut style.height,
+        &mut style.min_width, &mut style.min_height,
+        &mut style.max_width, &mut style.max_height,
+        &mut style.flex_basis, &mut style.row_gap, &mut style.column_gap,
+    ] {
+        scale(val);
+    }
+    for rect in [&mut style.margin, &mut style.padding, &mut style.border] {
+        for val in [&mut rect.left, &mut rect.right, &mut rect.top, &mut rect.bottom] {
+            scale(val);
+        }
     }
 }
@@ -1,72 +1,177 @@
 use bevy::prelude::*;
-use bevy::input::touch::TouchPhase;
+use bevy::input::{
+    touch::TouchPhase,
+    gamepad::{GamepadConnection, GamepadConnectionEvent}
+};
 use crate::{
     GameState,
     MenuState,
     components::*, 
-    resources::{SnakeSegments, TouchPosition},
+    resources::{Simulation, TouchPosition, GamepadSteer, GameSetting, GameMode},
+    keymap::{KeyMap, Action},
+    sim::{UP, DOWN, LEFT, RIGHT},
 };
 
+/// how far the left stick has to be pushed to steer
+pub const STICK_DEADZONE: f32 = 0.5;
+
 pub fn keyboard(
     kb_input: Res<Input<KeyCode>>,
-    mut heads: Query<&mut SnakeHead>,
-    bodies: Query<&Position, With<SnakeSegment>>,
-    segments: Res<SnakeSegments>
+    keymap: Res<KeyMap>,
+    game_mode: Res<GameMode>,
+    mut heads: Query<(&SnakeHead, &mut InputQueue), Without<Bot>>,
+    sim: Res<Simulation>
 ) {
-    let mut head = heads.single_mut();
-    // detect if new direction against the body
-    let head_id = segments.0.first().unwrap();
-    let next_head_id = segments.0.iter().nth(1).unwrap();
-    let head_pos = bodies.get(*head_id).unwrap();
-    let next_pos = bodies.get(*next_head_id).unwrap();
-    let (dx, dy) = (head_pos.0 - next_pos.0).into();
-
-    let mut direction = IVec2::ZERO;
-    if kb_input.any_just_pressed([KeyCode::Left, KeyCode::A, KeyCode::H]) {
-        if (dx, dy) == (1, 0) {
-            return;
+    for (head, mut queue) in heads.iter_mut() {
+        // a solo player steers with the keys of both players
+        let player = match *game_mode {
+            GameMode::Solo => None,
+            GameMode::Versus => Some(head.index),
+        };
+        if let Some(direction) = keymap.direction(player, &kb_input) {
+            queue.push(direction, head, &sim.0);
         }
-        direction = IVec2::new(-1, 0);
     }
-    else if kb_input.any_just_pressed([KeyCode::Right, KeyCode::D, KeyCode::L]) {
-        if (dx, dy) == (-1, 0) {
-            return;
+}
+
+/// collect the directions pushed on every gamepad this frame
+pub fn read_gamepads(
+    gamepads: Res<Gamepads>,
+    buttons: Res<Input<GamepadButton>>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut steer: ResMut<GamepadSteer>
+) {
+    steer.just_pressed.clear();
+    for gamepad in gamepads.iter() {
+        for (button, direction) in [
+            (GamepadButtonType::DPadUp, UP),
+            (GamepadButtonType::DPadDown, DOWN),
+            (GamepadButtonType::DPadLeft, LEFT),
+            (GamepadButtonType::DPadRight, RIGHT),
+        ] {
+            if buttons.just_pressed(GamepadButton::new(gamepad, button)) {
+                steer.just_pressed.push((gamepad, direction));
+            }
+        }
+
+        // the stick steers along its dominant axis once out of the deadzone
+        let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))
+            .unwrap_or_default();
+        let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))
+            .unwrap_or_default();
+        let stick = if x.abs().max(y.abs()) < STICK_DEADZONE {
+            IVec2::ZERO
+        }
+        else if x.abs() > y.abs() {
+            if x > 0. { RIGHT } else { LEFT }
+        }
+        else if y > 0. {
+            UP
+        }
+        else {
+            DOWN
+        };
+        let last = steer.sticks.insert(gamepad, stick).unwrap_or_default();
+        if stick != IVec2::ZERO && stick != last {
+            steer.just_pressed.push((gamepad, stick));
         }
-        direction = IVec2::new(1, 0);
     }
-    else if kb_input.any_just_pressed([KeyCode::Up, KeyCode::W, KeyCode::K]) {
-        if (dx, dy) == (0, -1) {
-            return;
+}
+
+/// log gamepads coming and going, a snake whose gamepad is gone takes
+/// any gamepad again
+pub fn gamepad_connections(
+    mut connection_evr: EventReader<GamepadConnectionEvent>,
+    mut steer: ResMut<GamepadSteer>,
+    mut game_settings: ResMut<GameSetting>
+) {
+    for event in connection_evr.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => {
+                info!("gamepad {} connected: {}", event.gamepad.id, info.name);
+            },
+            GamepadConnection::Disconnected => {
+                info!("gamepad {} disconnected", event.gamepad.id);
+                steer.sticks.remove(&event.gamepad);
+                for slot in game_settings.gamepads.iter_mut() {
+                    if *slot == Some(event.gamepad) {
+                        *slot = None;
+                    }
+                }
+            }
         }
-        direction = IVec2::new(0, 1);
     }
-    else if kb_input.any_just_pressed([KeyCode::Down, KeyCode::S, KeyCode::J]) {
-        if (dx, dy) == (0, 1) {
-            return;
+}
+
+pub fn gamepad(
+    steer: Res<GamepadSteer>,
+    gamepads: Res<Gamepads>,
+    game_settings: Res<GameSetting>,
+    game_mode: Res<GameMode>,
+    mut heads: Query<(&SnakeHead, &mut InputQueue), Without<Bot>>,
+    sim: Res<Simulation>
+) {
+    for (head, mut queue) in heads.iter_mut() {
+        // without a chosen gamepad a solo player takes any of them and the
+        // versus players share the connected ones in order
+        let pad = game_settings.gamepads[head.index];
+        let direction = match (pad, *game_mode) {
+            (Some(_), _) | (None, GameMode::Solo) => steer.direction(pad),
+            (None, GameMode::Versus) => {
+                let mut connected: Vec<Gamepad> = gamepads.iter().collect();
+                connected.sort_by_key(|gamepad| gamepad.id);
+                connected
+                    .get(head.index)
+                    .and_then(|gamepad| steer.direction(Some(*gamepad)))
+            }
+        };
+        if let Some(direction) = direction {
+            queue.push(direction, head, &sim.0);
         }
-        direction = IVec2::new(0, -1);
     }
+}
 
-    if direction != IVec2::ZERO {
-        head.direction = direction;
+/// the computer picks the next move of its snakes, once the players are
+/// under way
+pub fn bots(
+    mut bots: Query<(&mut SnakeHead, &Bot)>,
+    players: Query<&SnakeHead, Without<Bot>>,
+    sim: Res<Simulation>
+) {
+    // nobody to wait for in the demo
+    if !players.is_empty() && players.iter().all(|head| head.direction == IVec2::ZERO) {
+        return;
+    }
+    for (mut head, Bot(strategy)) in bots.iter_mut() {
+        head.direction = strategy.choose(&sim.0, head.index);
     }
 }
 
+/// true if `button_type` was just pressed on any gamepad
+pub fn gamepad_just_pressed(
+    buttons: &Input<GamepadButton>,
+    button_type: GamepadButtonType
+) -> bool {
+    buttons.get_just_pressed().any(|button| button.button_type == button_type)
+}
+
 pub fn pause(
     kb_input: Res<Input<KeyCode>>,
+    keymap: Res<KeyMap>,
+    buttons: Res<Input<GamepadButton>>,
     mut game_state: ResMut<NextState<GameState>>,
     mut menu_state: ResMut<NextState<MenuState>>,
 ) {
-    if kb_input.any_just_pressed([KeyCode::Space, KeyCode::Escape]) {
+    if keymap.just_pressed(Action::Pause, &kb_input) ||
+        gamepad_just_pressed(&buttons, GamepadButtonType::Start) {
         game_state.set(GameState::Menu);
         menu_state.set(MenuState::Pause);
     }
 }
 pub fn touch_input(
     touches: Res<Touches>,
-    mut heads: Query<&mut SnakeHead>,
-    bodies: Query<&Position, With<SnakeSegment>>,
-    segments: Res<SnakeSegments>
+    mut heads: Query<(&SnakeHead, &mut InputQueue), Without<Bot>>,
+    sim: Res<Simulation>
 ) {
     for finger in touches.iter_just_released() {
         // info!("just released finger {:?}", finger.id());
@@ -80,21 +185,21 @@ pub fn touch_input(
         if dx.abs() > dy.abs() {
             if dx > 0. {
                 // swipe right
-                handle_swipe(IVec2::new(1, 0), &mut heads, &bodies, &segments);
+                handle_swipe(IVec2::new(1, 0), &mut heads, &sim);
             }
             else {
                 // swipe left
-                handle_swipe(IVec2::new(-1, 0), &mut heads, &bodies, &segments);
+                handle_swipe(IVec2::new(-1, 0), &mut heads, &sim);
             }
         }
         else {
             if dy < 0. {
                 // swipe down
-                handle_swipe(IVec2::new(0, 1), &mut heads, &bodies, &segments);
+                handle_swipe(IVec2::new(0, 1), &mut heads, &sim);
             }
             else {
                 // swipe up
-                handle_swipe(IVec2::new(0, -1), &mut heads, &bodies, &segments);
+                handle_swipe(IVec2::new(0, -1), &mut heads, &sim);
             }
         }
     }
@@ -103,9 +208,8 @@ pub fn touch_input(
 pub fn _touch_events(
     mut touch_evr: EventReader<TouchInput>,
     mut touch_start: ResMut<TouchPosition>,
-    mut heads: Query<&mut SnakeHead>,
-    bodies: Query<&Position, With<SnakeSegment>>,
-    segments: Res<SnakeSegments>
+    mut heads: Query<(&SnakeHead, &mut InputQueue), Without<Bot>>,
+    sim: Res<Simulation>
 ) {
     for touch_input in touch_evr.read() {
         match touch_input.phase {
@@ -119,21 +223,21 @@ pub fn _touch_events(
                 if dx.abs() > dy.abs() {
                     if dx > 0. {
                         // swipe right
-                        handle_swipe(IVec2::new(1, 0), &mut heads, &bodies, &segments);
+                        handle_swipe(IVec2::new(1, 0), &mut heads, &sim);
                     }
                     else {
                         // swipe left
-                        handle_swipe(IVec2::new(-1, 0), &mut heads, &bodies, &segments);
+                        handle_swipe(IVec2::new(-1, 0), &mut heads, &sim);
                     }
                 }
                 else {
                     if dy < 0. {
                         // swipe down
-                        handle_swipe(IVec2::new(0, 1), &mut heads, &bodies, &segments);
+                        handle_swipe(IVec2::new(0, 1), &mut heads, &sim);
                     }
                     else {
                         // swipe up
-                        handle_swipe(IVec2::new(0, -1), &mut heads, &bodies, &segments);
+                        handle_swipe(IVec2::new(0, -1), &mut heads, &sim);
                     }
                 }
                 touch_start.0 = Vec2::ZERO;
@@ -150,34 +254,11 @@ pub fn _touch_events(
 
 pub fn handle_swipe(
     swipe_to: IVec2,
-    heads: &mut Query<&mut SnakeHead>,
-    bodies: &Query<&Position, With<SnakeSegment>>,
-    segments: &Res<SnakeSegments>
+    heads: &mut Query<(&SnakeHead, &mut InputQueue), Without<Bot>>,
+    sim: &Res<Simulation>
 ) {
-    let mut head = heads.single_mut();
-    // detect if new direction against the body
-    let head_id = segments.0.first().unwrap();
-    let next_head_id = segments.0.iter().nth(1).unwrap();
-    let head_pos = bodies.get(*head_id).unwrap();
-    let next_pos = bodies.get(*next_head_id).unwrap();
-    let (dx, dy) = (head_pos.0 - next_pos.0).into();
-
-    if swipe_to == IVec2::new(-1, 0) && (dx, dy) == (1, 0) {
-        // swipe left, head move to right
-        return;
-    }
-    else if swipe_to == IVec2::new(1, 0) && (dx, dy) == (-1, 0) {
-        // swipe right, head move to left
-        return;
+    // the touch screen steers the first snake
+    if let Some((head, mut queue)) = heads.iter_mut().find(|(head, _)| head.index == 0) {
+        queue.push(swipe_to, head, &sim.0);
     }
-    else if swipe_to == IVec2::new(0, -1) && (dx, dy) == (0, 1) {
-        // swipe up, head move to down
-        return;
-    }
-    else if swipe_to == IVec2::new(0, 1) && (dx, dy) == (0, -1) {
-        // swipe down, head move to up
-        return;
-    }
-
-    head.direction = swipe_to;
 }
@@ -0,0 +1,231 @@
+//! Keyboard bindings of the game and menu actions, rebound on the controls
+//! screen and persisted with `storage`.
+//!
+//! The first player steers with `Up`..`Right` and the second one with
+//! `P2Up`..`P2Right` in a versus game, a solo player and the menus take
+//! either.
+
+use std::collections::HashMap;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{
+    sim::{UP, DOWN, LEFT, RIGHT},
+    locale::Locale,
+    storage
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Action {
+    Up,
+    Down,
+    Left,
+    Right,
+    P2Up,
+    P2Down,
+    P2Left,
+    P2Right,
+    Pause,
+    Confirm,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 11] = [
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::P2Up,
+        Action::P2Down,
+        Action::P2Left,
+        Action::P2Right,
+        Action::Pause,
+        Action::Confirm,
+        Action::Back,
+    ];
+
+    /// steering actions of each player with their direction
+    pub const STEERING: [[(Action, IVec2); 4]; 2] = [
+        [
+            (Action::Left, LEFT),
+            (Action::Right, RIGHT),
+            (Action::Up, UP),
+            (Action::Down, DOWN),
+        ],
+        [
+            (Action::P2Left, LEFT),
+            (Action::P2Right, RIGHT),
+            (Action::P2Up, UP),
+            (Action::P2Down, DOWN),
+        ],
+    ];
+
+    pub fn label<'a>(&self, locale: &'a Locale) -> &'a str {
+        locale.get(match self {
+            Action::Up => "action_up",
+            Action::Down => "action_down",
+            Action::Left => "action_left",
+            Action::Right => "action_right",
+            Action::P2Up => "action_p2_up",
+            Action::P2Down => "action_p2_down",
+            Action::P2Left => "action_p2_left",
+            Action::P2Right => "action_p2_right",
+            Action::Pause => "action_pause",
+            Action::Confirm => "action_confirm",
+            Action::Back => "action_back",
+        })
+    }
+
+    /// read while playing
+    fn in_game(&self) -> bool {
+        !matches!(self, Action::Confirm | Action::Back)
+    }
+
+    /// read on the menus
+    fn in_menu(&self) -> bool {
+        matches!(self,
+            Action::Up | Action::Down | Action::P2Up | Action::P2Down |
+            Action::Confirm | Action::Back)
+    }
+
+    /// true if both actions are read at the same time, so they can't share
+    /// a key
+    fn overlaps(&self, other: Action) -> bool {
+        (self.in_game() && other.in_game()) || (self.in_menu() && other.in_menu())
+    }
+
+    fn default_keys(&self) -> Vec<KeyCode> {
+        match self {
+            Action::Up => vec![KeyCode::W, KeyCode::K],
+            Action::Down => vec![KeyCode::S, KeyCode::J],
+            Action::Left => vec![KeyCode::A, KeyCode::H],
+            Action::Right => vec![KeyCode::D, KeyCode::L],
+            Action::P2Up => vec![KeyCode::Up],
+            Action::P2Down => vec![KeyCode::Down],
+            Action::P2Left => vec![KeyCode::Left],
+            Action::P2Right => vec![KeyCode::Right],
+            Action::Pause => vec![KeyCode::Space, KeyCode::Escape],
+            Action::Confirm => vec![KeyCode::Return, KeyCode::Space],
+            Action::Back => vec![KeyCode::Escape],
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Resource, Serialize, Deserialize)]
+pub struct KeyMap(HashMap<Action, Vec<KeyCode>>);
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self(Action::ALL
+            .iter()
+            .map(|action| (*action, action.default_keys()))
+            .collect())
+    }
+}
+
+impl KeyMap {
+    const KEY: &'static str = "keymap";
+
+    /// the saved keymap, actions it lacks keep their default keys
+    pub fn load() -> Self {
+        let mut keymap = Self::default();
+        if let Some(saved) = storage::load::<KeyMap>(Self::KEY) {
+            keymap.0.extend(saved.0);
+        }
+        keymap
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::KEY, self);
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        self.0.get(&action).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn just_pressed(&self, action: Action, kb_input: &Input<KeyCode>) -> bool {
+        kb_input.any_just_pressed(self.keys(action).iter().copied())
+    }
+
+    /// the direction `player` pressed this frame, any player's if `None`
+    pub fn direction(&self, player: Option<usize>, kb_input: &Input<KeyCode>) -> Option<IVec2> {
+        Action::STEERING
+            .iter()
+            .enumerate()
+            .filter(|(p, _)| player.is_none_or(|player| player == *p))
+            .flat_map(|(_, steering)| steering.iter())
+            .find(|(action, _)| self.just_pressed(*action, kb_input))
+            .map(|(_, direction)| *direction)
+    }
+
+    /// another action read together with `action` that `key` is bound to
+    pub fn conflict(&self, action: Action, key: KeyCode) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|other| *other != action &&
+                other.overlaps(action) &&
+                self.keys(*other).contains(&key))
+    }
+
+    /// make `key` the only key of `action`
+    pub fn bind(&mut self, action: Action, key: KeyCode) {
+        self.0.insert(action, vec![key]);
+    }
+
+    /// the keys of `action` for its button on the controls screen
+    pub fn label(&self, action: Action, locale: &Locale) -> String {
+        let keys = self.keys(action)
+            .iter()
+            .map(|key| format!("{key:?}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        format!("{}: {keys}", action.label(locale))
+    }
+}
+
+/// the action waiting for a key on the controls screen
+#[derive(Debug, Default, Resource)]
+pub struct Rebinding(pub Option<Action>);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn actions_read_while_playing_cannot_share_a_key() {
+        let keymap = KeyMap::default();
+        assert_eq!(keymap.conflict(Action::Left, KeyCode::W), Some(Action::Up));
+        assert_eq!(keymap.conflict(Action::P2Up, KeyCode::W), Some(Action::Up));
+        assert_eq!(keymap.conflict(Action::Pause, KeyCode::Up), Some(Action::P2Up));
+        // a key already bound to the action itself is fine
+        assert_eq!(keymap.conflict(Action::Up, KeyCode::W), None);
+    }
+
+    #[test]
+    fn actions_read_on_the_menus_cannot_share_a_key() {
+        let keymap = KeyMap::default();
+        assert_eq!(keymap.conflict(Action::Back, KeyCode::Return), Some(Action::Confirm));
+        // moving up and down works on the menus as well
+        assert_eq!(keymap.conflict(Action::Back, KeyCode::S), Some(Action::Down));
+    }
+
+    #[test]
+    fn a_game_action_and_a_menu_action_may_share_a_key() {
+        let keymap = KeyMap::default();
+        // space pauses in a game and confirms on a menu
+        assert_eq!(keymap.conflict(Action::Confirm, KeyCode::Space), None);
+        assert_eq!(keymap.conflict(Action::Pause, KeyCode::Return), None);
+        assert_eq!(keymap.conflict(Action::Back, KeyCode::A), None);
+    }
+
+    #[test]
+    fn bind_replaces_every_key_of_the_action() {
+        let mut keymap = KeyMap::default();
+        keymap.bind(Action::Up, KeyCode::I);
+        assert_eq!(keymap.keys(Action::Up), &[KeyCode::I]);
+        // the old keys are free again
+        assert_eq!(keymap.conflict(Action::Left, KeyCode::W), None);
+        assert_eq!(keymap.conflict(Action::Left, KeyCode::K), None);
+        assert_eq!(keymap.conflict(Action::Left, KeyCode::I), Some(Action::Up));
+    }
+}
@@ -0,0 +1,285 @@
+//! Obstacle maps stored as RON under `assets/levels/`.
+//!
+//! ```ron
+//! (
+//!     cols: 9,
+//!     rows: 15,
+//!     obstacles: [(0, 3), (0, 4)],
+//!     snake: (head: (1, 0), direction: (1, 0), length: 3),
+//!     rivals: [(head: (-1, -5), direction: (-1, 0))],
+//!     fruit: Some((0, -4)),
+//!     goal: Some(Fruits(5)),
+//! )
+//! ```
+//! Cells use the board coordinates of `sim::Board`, `(0, 0)` is the middle.
+//! `obstacles`, `rivals`, `fruit` and `goal` may be left out, a snake grows
+//! behind its head opposite to `direction`. `rivals` are where the other
+//! snakes of a versus game start, the first one defaults to `snake` mirrored
+//! across the middle. The goal only counts in the campaign.
+
+use std::time::Duration;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use crate::{
+    sim::{Board, Snake, SnakeSim},
+    locale::Locale
+};
+
+/// locale key of the name and asset path of the shipped levels
+pub const LEVELS: [(&str, &str); 3] = [
+    ("level_pillars", "levels/pillars.level.ron"),
+    ("level_cross", "levels/cross.level.ron"),
+    ("level_corridors", "levels/corridors.level.ron"),
+];
+
+/// asset paths of the campaign stages, played in this order
+pub const CAMPAIGN: [&str; 4] = [
+    "levels/open.level.ron",
+    "levels/pillars.level.ron",
+    "levels/cross.level.ron",
+    "levels/corridors.level.ron",
+];
+
+/// what clears a campaign stage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Goal {
+    /// eat this many fruits
+    Fruits(u32),
+    /// grow to this many segments
+    Length(u32),
+    /// stay alive for this many seconds
+    Survive(u32),
+}
+
+impl Goal {
+    /// progress of `snake` after `play_time`, and the target
+    pub fn progress(&self, snake: &Snake, play_time: Duration) -> (u32, u32) {
+        match *self {
+            Goal::Fruits(n) => (snake.fruits_eaten().min(n), n),
+            Goal::Length(l) => ((snake.len() as u32).min(l), l),
+            Goal::Survive(t) => ((play_time.as_secs() as u32).min(t), t),
+        }
+    }
+
+    pub fn is_reached(&self, snake: &Snake, play_time: Duration) -> bool {
+        let (done, target) = self.progress(snake, play_time);
+        done >= target
+    }
+
+    pub fn label(&self, locale: &Locale) -> String {
+        match self {
+            Goal::Fruits(n) => locale.format("goal_fruits", &[("n", n)]),
+            Goal::Length(l) => locale.format("goal_length", &[("n", l)]),
+            Goal::Survive(t) => locale.format("goal_survive", &[("n", t)]),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Asset, TypePath)]
+pub struct Level {
+    pub cols: u32,
+    pub rows: u32,
+    /// cells that kill the snake
+    #[serde(default)]
+    pub obstacles: Vec<(i32, i32)>,
+    pub snake: SnakeSpawn,
+    #[serde(default)]
+    pub rivals: Vec<SnakeSpawn>,
+    #[serde(default)]
+    pub fruit: Option<(i32, i32)>,
+    #[serde(default)]
+    pub goal: Option<Goal>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SnakeSpawn {
+    pub head: (i32, i32),
+    pub direction: (i32, i32),
+    #[serde(default = "SnakeSpawn::default_length")]
+    pub length: u32,
+}
+
+impl SnakeSpawn {
+    fn default_length() -> u32 {
+        3
+    }
+
+    /// cells from the head to the tail
+    pub fn body(&self) -> Vec<IVec2> {
+        let head = IVec2::from(self.head);
+        let direction = IVec2::from(self.direction);
+        (0..self.length.max(2) as i32)
+            .map(|i| head - direction * i)
+            .collect()
+    }
+}
+
+impl Level {
+    pub fn board(&self) -> Board {
+        Board::new(self.cols, self.rows)
+    }
+
+    pub fn obstacles(&self) -> impl Iterator<Item = IVec2> + '_ {
+        self.obstacles.iter().map(|cell| IVec2::from(*cell))
+    }
+
+    /// where the rival `index` starts, `None` if the level has no room
+    /// for it
+    pub fn rival(&self, index: usize) -> Option<Vec<IVec2>> {
+        match self.rivals.get(index) {
+            Some(spawn) => Some(spawn.body()),
+            None if index == 0 => {
+                let board = self.board();
+                Some(self.snake.body().iter().map(|cell| board.mirror(*cell)).collect())
+            },
+            None => None
+        }
+    }
+
+    /// a fresh game on this level with `rivals` more snakes, the ones
+    /// without a spawn of their own go wherever they fit, `None` if they
+    /// don't
+    pub fn sim(&self, rivals: usize) -> Option<SnakeSim> {
+        let mut sim = SnakeSim::with_snake(self.board(), self.snake.body())
+            .with_obstacles(self.obstacles());
+        for index in 0..rivals {
+            let body = self.rival(index)
+                .filter(|body| body.iter().all(|cell| sim.is_free(*cell)))
+                .or_else(|| sim.free_spawn(self.snake.length as usize))?;
+            sim = sim.with_rival(body);
+        }
+        // a rival may sit on the fruit, it is placed at random then
+        if let Some(fruit) = self.fruit.map(IVec2::from).filter(|cell| sim.is_free(*cell)) {
+            sim.set_fruit(Some(fruit));
+        }
+        Some(sim)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let board = self.board();
+        if self.cols < 3 || self.rows < 3 {
+            return Err(format!("board {}x{} is too small", self.cols, self.rows));
+        }
+        let direction = IVec2::from(self.snake.direction);
+        if direction.x.abs() + direction.y.abs() != 1 {
+            return Err(format!("snake direction {direction} is not a unit step"));
+        }
+        if let Some(cell) = self.obstacles().find(|cell| !board.contains(*cell)) {
+            return Err(format!("obstacle {cell} is off the board"));
+        }
+        let mut snakes = self.snake.body();
+        for spawn in &self.rivals {
+            let direction = IVec2::from(spawn.direction);
+            if direction.x.abs() + direction.y.abs() != 1 {
+                return Err(format!("rival direction {direction} is not a unit step"));
+            }
+            snakes.extend(spawn.body());
+        }
+        for (i, cell) in snakes.iter().enumerate() {
+            if !board.contains(*cell) ||
+                self.obstacles().any(|o| o == *cell) ||
+                snakes[..i].contains(cell) {
+                return Err(format!("snake cell {cell} is not free"));
+            }
+        }
+        if let Some(fruit) = self.fruit.map(IVec2::from) {
+            if !board.contains(fruit) ||
+                self.obstacles().any(|o| o == fruit) ||
+                self.snake.body().contains(&fruit) {
+                return Err(format!("fruit {fruit} is not free"));
+            }
+        }
+        if let Some(Goal::Length(l)) = self.goal {
+            if l as usize > board.cell_count() - self.obstacles.len() {
+                return Err(format!("goal length {l} does not fit on the board"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct LevelLoader;
+
+impl AssetLoader for LevelLoader {
+    type Asset = Level;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Level, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let level: Level = ron::de::from_bytes(&bytes)?;
+            level.validate()?;
+            Ok(level)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["level.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(path: &str) -> Level {
+        let path = format!("{}/assets/{path}", env!("CARGO_MANIFEST_DIR"));
+        let text = std::fs::read_to_string(&path).expect("the level file is there");
+        ron::de::from_str(&text).unwrap_or_else(|err| panic!("{path}: {err}"))
+    }
+
+    /// a 9x15 level with a snake of three facing right from `head`
+    fn open_level(head: (i32, i32)) -> Level {
+        Level {
+            cols: 9,
+            rows: 15,
+            obstacles: Vec::new(),
+            snake: SnakeSpawn { head, direction: (1, 0), length: 3 },
+            rivals: Vec::new(),
+            fruit: None,
+            goal: None,
+        }
+    }
+
+    #[test]
+    fn the_shipped_levels_are_valid() {
+        let paths = LEVELS.iter().map(|(_, path)| *path).chain(CAMPAIGN);
+        for path in paths {
+            assert_eq!(load(path).validate(), Ok(()), "{path}");
+        }
+    }
+
+    #[test]
+    fn a_spawn_on_an_obstacle_is_rejected() {
+        let level = Level { obstacles: vec![(-1, 0)], ..open_level((0, 0)) };
+        assert!(level.validate().is_err());
+    }
+
+    #[test]
+    fn a_cell_off_the_board_is_rejected() {
+        let level = Level { obstacles: vec![(5, 0)], ..open_level((0, 0)) };
+        assert!(level.validate().is_err());
+        // the tail sticks out past the left edge
+        assert!(open_level((-4, 0)).validate().is_err());
+    }
+
+    #[test]
+    fn a_rival_on_the_player_is_rejected() {
+        let rival = SnakeSpawn { head: (-1, 2), direction: (0, 1), length: 3 };
+        let level = Level { rivals: vec![rival], ..open_level((0, 0)) };
+        assert!(level.validate().is_err());
+    }
+}
@@ -1,14 +1,29 @@
+// bevy systems take many parameters and nested query types
+#![allow(clippy::too_many_arguments, clippy::type_complexity)]
+
 use bevy::prelude::*;
+use bevy::input::InputSystem;
 //use bevy::window::PresentMode;
 use resources::{
     GameSetting, 
-    GameAssets
+    GameAssets,
+    HighScores,
+    CampaignProgress,
+    GamepadSteer
 };
+use keymap::KeyMap;
+use replay::Replays;
+use save::SaveSlot;
+use theme::{Palette, Theme, ThemeLoader};
+use locale::{Locale, Strings, StringsLoader};
 use components::MainCamera;
 use states::*;
 
 mod game_plugin;
 mod menu_plugin;
+mod sound_plugin;
+mod demo_plugin;
+mod replay_plugin;
 mod control;
 mod input;
 mod events;
@@ -16,37 +31,44 @@ mod components;
 mod resources;
 mod graphics;
 mod states;
+mod storage;
+mod level;
+mod keymap;
+mod replay;
+mod save;
+mod theme;
+mod locale;
+pub mod sim;
+pub mod bot;
 
 mod prelude {
-    use bevy::prelude::*;
-    pub const GAME_FPS: u64 = (1000. / 60. * 40. / 3.) as u64;
-    pub const TEXT_COLOR: Color = Color::rgb(0.9, 0.9, 0.9);
-    pub const NORMAL_BUTTON: Color = Color::rgb(0.15, 0.15, 0.15);
-    pub const HOVERED_BUTTON: Color = Color::rgb(0.25, 0.25, 0.25);
-    pub const HOVERED_PRESSED_BUTTON: Color = Color::rgb(0.25, 0.65, 0.25);
-    pub const PRESSED_BUTTON: Color = Color::rgb(0.35, 0.75, 0.35);
     pub const WALL_WIDTH: f32 = 10.;
-    pub const WALL_COLOR: Color = Color::LIME_GREEN;
     pub const BOARD_ROWS: u32 = 15;
     pub const BOARD_COLS: u32 = 9;
     pub const BOARD_OFFSET_Y: f32 = 40.0;
+    /// width of the score bar beside the board in a landscape window
+    pub const HUD_WIDTH: f32 = 160.0;
+    /// side of a cell at a scale of 1
+    pub const SNAKE_WIDTH: f32 = 40.0;
 }
 
 pub struct SnakeGame;
 
 impl SnakeGame {
     pub fn run() {
+        let game_settings = GameSetting {
+            seed: seed_arg(),
+            ..GameSetting::load()
+        };
         let mut app = App::new();
         app
             .add_plugins(DefaultPlugins.set(
                 WindowPlugin {
                     primary_window: Some(
                             Window { 
-                                resolution: (
-                                    9. * 40. + 2. * 10.,
-                                    15. * 40. + 2. * 10. + 40.
-                                ).into(), 
-                                title: "貪食蛇".to_string(), 
+                                resolution: game_settings
+                                    .window_size().into(), 
+                                title: Locale::default().get("title").to_string(), 
                                 fit_canvas_to_parent: true,
                                 ..default()
                             }
@@ -55,18 +77,45 @@ impl SnakeGame {
                 })
             )
             .add_state::<GameState>()
-            .insert_resource(GameSetting::default())
+            .insert_resource(game_settings)
+            .insert_resource(HighScores::load())
+            .insert_resource(CampaignProgress::load())
+            .insert_resource(KeyMap::load())
+            .insert_resource(Replays::load())
+            .insert_resource(SaveSlot::load())
+            .init_resource::<GamepadSteer>()
+            .init_resource::<Palette>()
+            .init_asset::<Theme>()
+            .init_asset_loader::<ThemeLoader>()
+            .init_resource::<Locale>()
+            .init_asset::<Strings>()
+            .init_asset_loader::<StringsLoader>()
             .add_systems(Startup, 
                 (
                     env_setup, 
-                    GameAssets::load_assets, 
-                    #[cfg(target_arch = "wasm32")]
-                    graphics::setup_ui
+                    GameAssets::load_assets
                 ).chain()
             )
-            //.add_systems(Update, graphics::on_size_changed)
+            .add_systems(PreUpdate, input::read_gamepads.after(InputSystem))
+            .add_systems(Update, (
+                input::gamepad_connections,
+                graphics::resize_window
+                    .run_if(resource_changed::<GameSetting>()),
+                graphics::toggle_fullscreen,
+                graphics::apply_theme,
+                graphics::redraw_grid
+                    .run_if(resource_changed::<GameSetting>()),
+                graphics::orient.before(graphics::on_size_changed),
+                graphics::on_size_changed,
+            ))
             //.add_systems(Update, toggle_vsync)
-            .add_plugins((menu_plugin::MenuPlugin, game_plugin::GamePlugin));
+            .add_plugins((
+                menu_plugin::MenuPlugin,
+                game_plugin::GamePlugin,
+                sound_plugin::SoundPlugin,
+                demo_plugin::DemoPlugin,
+                replay_plugin::ReplayPlugin
+            ));
 
         // #[cfg(feature = "debug")]
         // app
@@ -77,6 +126,18 @@ impl SnakeGame {
     }
 }
 
+/// `--seed N` on the command line plays every game with that seed
+fn seed_arg() -> Option<u64> {
+    let value = std::env::args().skip_while(|arg| arg != "--seed").nth(1)?;
+    match value.parse() {
+        Ok(seed) => Some(seed),
+        Err(_) => {
+            eprintln!("ignoring seed {value:?}, it is not a number");
+            None
+        }
+    }
+}
+
 fn env_setup(mut commands: Commands) {
     commands.spawn((Camera2dBundle::default(), MainCamera));
 }
@@ -0,0 +1,169 @@
+//! Text of the menus and the play bar in every language, key/value tables
+//! stored as RON under `assets/locales/`.
+//!
+//! ```ron
+//! {
+//!     "new_game": "New game",
+//!     "score": "Score: {score}",
+//! }
+//! ```
+//! `{name}` is filled in by `Locale::format`. A key missing from a table
+//! falls back to the Traditional Chinese one, built into the game, then to
+//! the key itself.
+
+use std::{collections::HashMap, fmt::Display};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Language {
+    #[default]
+    ZhTw,
+    En,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::ZhTw, Language::En];
+
+    /// asset path of the table
+    pub fn path(&self) -> &'static str {
+        match self {
+            Language::ZhTw => "locales/zh-TW.locale.ron",
+            Language::En => "locales/en.locale.ron",
+        }
+    }
+
+    /// the name of the language in itself
+    pub fn label(&self) -> &'static str {
+        match self {
+            Language::ZhTw => "繁體中文",
+            Language::En => "English",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|l| l == self).unwrap_or_default();
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+}
+
+/// the strings of one language
+#[derive(Debug, Default, Clone, Deserialize, Asset, TypePath)]
+#[serde(transparent)]
+pub struct Strings(pub HashMap<String, String>);
+
+/// the strings of the language on screen
+#[derive(Debug, Resource)]
+pub struct Locale {
+    pub language: Language,
+    strings: Strings,
+    fallback: Strings,
+}
+
+impl Default for Locale {
+    /// Traditional Chinese, there before any asset is loaded
+    fn default() -> Self {
+        let fallback = ron::de::from_str(include_str!("../assets/locales/zh-TW.locale.ron"))
+            .expect("the built-in strings are valid");
+        Self {
+            language: Language::ZhTw,
+            strings: Strings::default(),
+            fallback,
+        }
+    }
+}
+
+impl Locale {
+    /// show `strings` of `language` from now on
+    pub fn set(&mut self, language: Language, strings: Strings) {
+        self.language = language;
+        self.strings = strings;
+    }
+
+    /// the text of `key`
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings.0
+            .get(key)
+            .or_else(|| self.fallback.0.get(key))
+            .map(String::as_str)
+            .unwrap_or(key)
+    }
+
+    /// the text of `key` with every `{name}` of `args` filled in
+    pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{name}}}"), &value.to_string());
+        }
+        text
+    }
+}
+
+#[derive(Default)]
+pub struct StringsLoader;
+
+impl AssetLoader for StringsLoader {
+    type Asset = Strings;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Strings, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["locale.ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// English with only two of its strings
+    fn partial() -> Locale {
+        let strings = ron::de::from_str(r#"{
+            "quit": "Quit",
+            "score": "Score: {score}",
+        }"#).expect("the table is valid");
+        let mut locale = Locale::default();
+        locale.set(Language::En, strings);
+        locale
+    }
+
+    #[test]
+    fn a_missing_key_falls_back_to_the_built_in_table() {
+        let locale = partial();
+        assert_eq!(locale.get("quit"), "Quit");
+        assert_eq!(locale.get("stage"), "第{stage}關");
+        // in no table at all
+        assert_eq!(locale.get("no_such_key"), "no_such_key");
+    }
+
+    #[test]
+    fn placeholders_are_filled_in() {
+        let locale = partial();
+        assert_eq!(locale.format("score", &[("score", &42)]), "Score: 42");
+        assert_eq!(locale.format("stage", &[("stage", &3)]), "第3關");
+        assert_eq!(
+            locale.format("replay_row", &[("date", &"10-18"), ("mode", &"solo"), ("score", &7)]),
+            "10-18 solo 7分"
+        );
+        // an argument without a placeholder changes nothing
+        assert_eq!(locale.format("quit", &[("score", &1)]), "Quit");
+    }
+}
@@ -6,17 +6,26 @@ use crate::{
     MenuState,
     GameAssets,
     despawn_screen,
-    components::*, resources::GameSetting,
+    components::*,
+    resources::{
+        GameSetting, HighScores, LastGame, Campaign, CampaignProgress, PlayTime,
+        GamepadSteer, GameMode, RoundResult, Demo, GameRng, Simulation, Layout,
+        SettingsReturn,
+        BOARD_PRESETS, BOARD_MIN_SIDE, BOARD_MAX_COLS, BOARD_MAX_ROWS, BOTS_MAX
+    },
+    sim::{DeathCause, Walls, UP, DOWN},
+    level::{LEVELS, CAMPAIGN},
+    theme::{Palette, ThemeRole, THEMES},
+    locale::{Locale, Language, Strings},
+    keymap::{KeyMap, Action, Rebinding},
+    input::gamepad_just_pressed,
+    control::score_label,
+    graphics::on_size_changed,
+    events::MenuActionEvent,
+    replay::{Replays, Playback},
+    save::SaveSlot,
 };
 
-#[derive(Component)]
-enum MenuButtonAction {
-    New,
-    Pause,
-    Resume,
-    Quit,
-}
-
 pub struct MenuPlugin;
 
 impl Plugin for MenuPlugin {
@@ -29,11 +38,65 @@ impl Plugin for MenuPlugin {
             .add_systems(OnEnter(MenuState::Pause), pause_menu_setup)
             .add_systems(OnExit(MenuState::Pause), 
                 despawn_screen::<OnPauseMenuScreen>)
+            .add_systems(OnEnter(MenuState::GameOver), game_over_menu_setup)
+            .add_systems(OnExit(MenuState::GameOver), 
+                despawn_screen::<OnGameOverScreen>)
+            .add_systems(OnEnter(MenuState::Board), board_menu_setup)
+            .add_systems(OnExit(MenuState::Board), 
+                despawn_screen::<OnBoardMenuScreen>)
+            .add_systems(OnEnter(MenuState::LevelComplete), level_complete_menu_setup)
+            .add_systems(OnExit(MenuState::LevelComplete), 
+                despawn_screen::<OnLevelCompleteScreen>)
+            .add_systems(OnEnter(MenuState::RoundOver), round_over_menu_setup)
+            .add_systems(OnExit(MenuState::RoundOver), 
+                despawn_screen::<OnRoundOverScreen>)
+            .add_systems(OnEnter(MenuState::Settings), settings_menu_setup)
+            .add_systems(OnExit(MenuState::Settings), 
+                despawn_screen::<OnSettingsScreen>)
+            .add_systems(OnEnter(MenuState::Controls), controls_menu_setup)
+            .add_systems(OnExit(MenuState::Controls), (
+                despawn_screen::<OnControlsScreen>,
+                stop_rebinding
+            ))
+            .add_systems(OnEnter(MenuState::Replays), replays_menu_setup)
+            .add_systems(OnExit(MenuState::Replays), 
+                despawn_screen::<OnReplaysScreen>)
+            .add_systems(OnEnter(MenuState::HighScores), high_score_menu_setup)
+            .add_systems(OnExit(MenuState::HighScores), 
+                despawn_screen::<OnHighScoreScreen>)
             .add_systems(Update, (
                 menu_action, 
                 button_system,
-                menu_keys.run_if(in_state(GameState::Menu))
-            ));
+                rebind_key
+                    .before(menu_keys)
+                    .run_if(in_state(MenuState::Controls)),
+                menu_keys.run_if(in_state(GameState::Menu)
+                    .or_else(in_state(GameState::Over))),
+                handle_menu_action
+                    .after(menu_action)
+                    .after(menu_keys),
+                option_texts.run_if(resource_changed::<GameSetting>()
+                    .or_else(resource_changed::<Locale>())),
+                relayout_play_bar
+                    .after(on_size_changed)
+                    .run_if(resource_changed::<GameSetting>()
+                        .or_else(resource_changed::<Locale>())),
+                binding_texts.run_if(resource_changed::<KeyMap>()
+                    .or_else(resource_changed::<Rebinding>())),
+                replay_rows.run_if(in_state(MenuState::Replays)
+                    .and_then(resource_changed::<Replays>())),
+                (
+                    apply_locale,
+                    (relocalize_menu, retitle_window)
+                        .run_if(resource_changed::<Locale>()),
+                )
+                    .chain()
+                    .before(option_texts)
+                    .before(relayout_play_bar),
+            ))
+            .init_resource::<Rebinding>()
+            .init_resource::<SettingsReturn>()
+            .add_event::<MenuActionEvent>();
     }
 }
 
@@ -41,12 +104,16 @@ fn main_menu_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     game_assets: Res<GameAssets>,
-    game_settings: Res<GameSetting>
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    progress: Res<CampaignProgress>,
+    slot: Res<SaveSlot>,
+    locale: Res<Locale>
 ) {
     let button_style = Style {
         width: Val::Px(250. * game_settings.game_scale),
-        height: Val::Px(65. * game_settings.game_scale),
-        margin: UiRect::all(Val::Px(20. * game_settings.game_scale)),
+        height: Val::Px(40. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(3. * game_settings.game_scale)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -58,8 +125,8 @@ fn main_menu_setup(
         ..default()
     };
     let button_text_style = TextStyle {
-        font_size: 40.0 * game_settings.game_scale,
-        color: TEXT_COLOR,
+        font_size: 36.0 * game_settings.game_scale,
+        color: palette.text,
         font: game_assets.cjk_font.clone(),
     };
 
@@ -78,48 +145,82 @@ fn main_menu_setup(
         ))
         .with_children(|parent| {
             parent
-                .spawn(NodeBundle {
+                .spawn((NodeBundle {
                     style: Style {
                         flex_direction: FlexDirection::Column,
                         align_items: AlignItems::Center,
                         ..default()
                     },
-                    background_color: Color::CRIMSON.into(),
+                    background_color: palette.panel.into(),
                     ..default()
-                })
+                    },
+                    ThemeRole::Panel,
+                ))
                 .with_children(|parent| {
                     // display the game name
-                    parent.spawn(
+                    parent.spawn((
                         TextBundle::from_section(
-                            /*"SNAKE GAME",*/ "貪食蛇",
+                            /*"SNAKE GAME",*/ locale.get("title"),
                             TextStyle {
                                 font_size: 80.0 * game_settings.game_scale,
-                                color: TEXT_COLOR,
+                                color: palette.text,
                                 font: game_assets.cjk_font.clone(),
                             },
                         )
                         .with_style(
                             Style { 
                                 margin: UiRect::all(
-                                    Val::Px(50. * game_settings.game_scale)),
+                                    Val::Px(20. * game_settings.game_scale)),
                                 ..default()
                             }
-                        )
-                    );
+                        ),
+                        ThemeRole::Text
+                    ));
                     // Display buttons
+                    //  - continue, if a game was saved
                     //  - new game
+                    //  - campaign
+                    //  - versus
+                    //  - board size
+                    //  - settings
+                    //  - high scores
+                    //  - replays
                     //  - quit
-                    parent.spawn((
+                    // the saved game is selected first when there is one
+                    let saved = slot.0.is_some();
+                    if saved {
+                        parent.spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: palette.button.into(),
+                                ..default()
+                            },
+                            ThemeRole::Button,
+                            MenuButtonAction::Continue,
+                            MenuItems,
+                            ToSelectOption
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(locale.get("continue"), button_text_style.clone()),
+                                ThemeRole::Text
+                            ));
+                        });
+                    }
+                    let mut new_game = parent.spawn((
                         ButtonBundle {
                             style: button_style.clone(),
-                            background_color: NORMAL_BUTTON.into(),
+                            background_color: palette.button.into(),
                             ..default()
                         },
+                        ThemeRole::Button,
                         MenuButtonAction::New,
-                        MenuItems,
-                        ToSelectOption
-                    ))
-                    .with_children(|parent| {
+                        MenuItems
+                    ));
+                    if !saved {
+                        new_game.insert(ToSelectOption);
+                    }
+                    new_game.with_children(|parent| {
                         let icon = asset_server.load(
                             "textures/game_icons/right.png");
                         parent.spawn(
@@ -131,19 +232,139 @@ fn main_menu_setup(
                                 ..default()
                             }
                         );
-                        parent.spawn(
+                        parent.spawn((
                             TextBundle::from_section(
-                            /*"NEW GAME",*/ "新遊戲",
+                            /*"NEW GAME",*/ locale.get("new_game"),
                                 button_text_style.clone()
-                            )
+                            ),
+                            ThemeRole::Text
+                        ));
+                    });
+                    let campaign_label = if progress.is_complete() {
+                        locale.get("campaign_done").to_string()
+                    }
+                    else if progress.cleared > 0 {
+                        locale.format("campaign_continue", &[
+                            ("stage", &(progress.next_stage() + 1)),
+                        ])
+                    }
+                    else {
+                        locale.get("campaign").to_string()
+                    };
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Campaign,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(campaign_label, button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Versus,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("versus"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Board,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("board"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Settings,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        let icon = asset_server.load(
+                            "textures/game_icons/wrench.png");
+                        parent.spawn(
+                            ImageBundle {
+                                style: button_icon_style.clone(),
+                                image: UiImage::new(icon),
+                                transform: Transform::from_scale(
+                                    Vec3::splat(game_settings.game_scale)),
+                                ..default()
+                            }
                         );
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("settings"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::HighScores,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("high_scores"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
                     });
                     parent.spawn((
                         ButtonBundle {
                             style: button_style.clone(),
-                            background_color: NORMAL_BUTTON.into(),
+                            background_color: palette.button.into(),
                             ..default()
                         },
+                        ThemeRole::Button,
+                        MenuButtonAction::Replays,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("replays"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
                         MenuButtonAction::Quit,
                         MenuItems
                     ))
@@ -156,7 +377,10 @@ fn main_menu_setup(
                                 Vec3::splat(game_settings.game_scale)),
                             ..default()
                         });
-                        parent.spawn(TextBundle::from_section("離開", button_text_style));
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("quit"), button_text_style),
+                            ThemeRole::Text
+                        ));
                     });
                 });
         });
@@ -166,19 +390,14 @@ fn pause_menu_setup(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     game_assets: Res<GameAssets>,
-    curr_game_state: Res<State<GameState>>,
-    mut menu_state: ResMut<NextState<MenuState>>,
-    game_settings: Res<GameSetting>
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    locale: Res<Locale>
 ) {
-    if curr_game_state.get() == &GameState::Over {
-        menu_state.set(MenuState::Main);
-        return;
-    }
-
     let button_style = Style {
         width: Val::Px(250. * game_settings.game_scale),
         height: Val::Px(65. * game_settings.game_scale),
-        margin: UiRect::all(Val::Px(20. * game_settings.game_scale)),
+        margin: UiRect::all(Val::Px(12. * game_settings.game_scale)),
         justify_content: JustifyContent::Center,
         align_items: AlignItems::Center,
         ..default()
@@ -191,7 +410,7 @@ fn pause_menu_setup(
     };
     let button_text_style = TextStyle {
         font_size: 40.0 * game_settings.game_scale,
-        color: TEXT_COLOR,
+        color: palette.text,
         font: game_assets.cjk_font.clone(),
     };
 
@@ -210,23 +429,25 @@ fn pause_menu_setup(
         ))
         .with_children(|parent| {
             parent
-                .spawn(NodeBundle {
+                .spawn((NodeBundle {
                     style: Style {
                         flex_direction: FlexDirection::Column,
                         align_items: AlignItems::Center,
                         ..default()
                     },
-                    background_color: Color::CRIMSON.into(),
+                    background_color: palette.panel.into(),
                     ..default()
-                })
+                    },
+                    ThemeRole::Panel,
+                ))
                 .with_children(|parent| {
                     // display the game name
-                    parent.spawn(
+                    parent.spawn((
                         TextBundle::from_section(
-                            "貪食蛇",
+                            locale.get("title"),
                             TextStyle {
                                 font_size: 80.0 * game_settings.game_scale,
-                                color: TEXT_COLOR,
+                                color: palette.text,
                                 font: game_assets.cjk_font.clone(),
                             },
                         )
@@ -236,36 +457,41 @@ fn pause_menu_setup(
                                     Val::Px(50. * game_settings.game_scale)),
                                 ..default()
                             }
-                        )
-                    );
+                        ),
+                        ThemeRole::Text
+                    ));
                     // Display buttons
                     //  - resume
                     //  - new game
+                    //  - settings
                     //  - quit
                     parent.spawn((
                         ButtonBundle {
                             style: button_style.clone(),
-                            background_color: NORMAL_BUTTON.into(),
+                            background_color: palette.button.into(),
                             ..default()
                         },
+                        ThemeRole::Button,
                         MenuButtonAction::Resume,
                         MenuItems,
                         ToSelectOption
                     ))
                     .with_children(|parent| {
-                        parent.spawn(
+                        parent.spawn((
                             TextBundle::from_section(
-                                "回到遊戲",
+                                locale.get("resume"),
                                 button_text_style.clone()
-                            )
-                        );
+                            ),
+                            ThemeRole::Text
+                        ));
                     });
                     parent.spawn((
                         ButtonBundle {
                             style: button_style.clone(),
-                            background_color: NORMAL_BUTTON.into(),
+                            background_color: palette.button.into(),
                             ..default()
                         },
+                        ThemeRole::Button,
                         MenuButtonAction::New,
                         MenuItems
                     ))
@@ -281,19 +507,48 @@ fn pause_menu_setup(
                                 ..default()
                             }
                         );
-                        parent.spawn(
+                        parent.spawn((
                             TextBundle::from_section(
-                            /*"NEW GAME",*/ "新遊戲",
+                            /*"NEW GAME",*/ locale.get("new_game"),
                                 button_text_style.clone()
-                            )
+                            ),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Settings,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        let icon = asset_server.load(
+                            "textures/game_icons/wrench.png");
+                        parent.spawn(
+                            ImageBundle {
+                                style: button_icon_style.clone(),
+                                image: UiImage::new(icon),
+                                transform: Transform::from_scale(
+                                    Vec3::splat(game_settings.game_scale)),
+                                ..default()
+                            }
                         );
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("settings"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
                     });
                     parent.spawn((
                         ButtonBundle {
                             style: button_style.clone(),
-                            background_color: NORMAL_BUTTON.into(),
+                            background_color: palette.button.into(),
                             ..default()
                         },
+                        ThemeRole::Button,
                         MenuButtonAction::Quit,
                         MenuItems
                     ))
@@ -306,237 +561,2063 @@ fn pause_menu_setup(
                                 Vec3::splat(game_settings.game_scale)),
                             ..default()
                         });
-                        parent.spawn(TextBundle::from_section(
-                            "離開", button_text_style)
-                        );
+                        parent.spawn((TextBundle::from_section(
+                            locale.get("quit"), button_text_style),
+                            ThemeRole::Text
+                        ));
                     });
                 });
         });
 }
 
-pub fn play_menu_setup(
+fn game_over_menu_setup(
     mut commands: Commands,
     game_assets: Res<GameAssets>,
-    game_settings: Res<GameSetting>
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    last_game: Option<Res<LastGame>>,
+    rng: Res<GameRng>,
+    locale: Res<Locale>
 ) {
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(65. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(10. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
     let button_text_style = TextStyle {
-        font_size: 25.0 * game_settings.game_scale,
-        color: TEXT_COLOR,
+        font_size: 40.0 * game_settings.game_scale,
+        color: palette.text,
         font: game_assets.cjk_font.clone(),
     };
+    let stat_text_style = TextStyle {
+        font_size: 28.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+
+    // final score, length, time survived, cause of death, record and seed
+    let mut stats = Vec::new();
+    if let Some(last_game) = last_game {
+        let entry = &last_game.entry;
+        stats.push(locale.format("score", &[("score", &entry.score)]));
+        stats.push(locale.format("length", &[("length", &entry.length)]));
+        stats.push(locale.format("time", &[("time", &entry.duration_label())]));
+        stats.push(locale.get(match last_game.cause {
+            DeathCause::Wall => "death_wall",
+            DeathCause::SelfCollision => "death_self",
+            DeathCause::Obstacle => "death_obstacle",
+            DeathCause::Snake => "death_snake",
+        }).to_string());
+        match last_game.rank {
+            Some(0) => stats.push(locale.get("new_record").to_string()),
+            Some(rank) => stats.push(locale.format("rank", &[("rank", &(rank + 1))])),
+            None => ()
+        }
+    }
+    stats.push(locale.format("seed", &[("seed", &rng.seed)]));
 
     commands
         .spawn((NodeBundle {
             style: Style {
-                display: Display::Grid,
                 width: Val::Percent(100.0),
-                height: Val::Px(BOARD_OFFSET_Y * game_settings.game_scale),
-                grid_template_columns: vec![
-                    GridTrack::percent(30.), 
-                    GridTrack::percent(30.),
-                    GridTrack::auto()
-                ],
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
                 ..default()
-            }, 
+            },
             ..default()
             },
-            OnPlayMenuScreen,
-            OnGameScreen,
-        )) 
+            OnGameOverScreen,
+        ))
         .with_children(|parent| {
-            // pause button
-            parent.spawn((
-                ButtonBundle {
+            parent
+                .spawn((NodeBundle {
                     style: Style {
-                        width: Val::Px(50. * game_settings.game_scale),
-                        height: Val::Auto,
-                        margin: UiRect::all(Val::Px(5. * game_settings.game_scale)),
-                        grid_column: GridPlacement::start(1),
-                        justify_content: JustifyContent::Center,
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
                         ..default()
                     },
-                    background_color: NORMAL_BUTTON.into(),
-                    ..default()
-                },
-                MenuButtonAction::Pause,
-            ))
-            .with_children(|parent| {
-                parent.spawn(
-                    TextBundle::from_section(
-                        "暫停",
-                        button_text_style.clone()
-                    )
-                );
-            });
-
-            // score board
-            parent.spawn((TextBundle::from_section(
-                    "分數: 0".to_string(), 
-                    button_text_style.clone()
-                ).with_style(Style {
-                    grid_column: GridPlacement::start(3),
-                    align_self: AlignSelf::Center,
-                    justify_self: JustifySelf::End,
-                    margin: UiRect::all(Val::Px(3. * game_settings.game_scale)),
+                    background_color: palette.panel.with_a(0.9).into(),
                     ..default()
-                }),
-                Score(0)
-            ));
-        }); 
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("game_over"),
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(30. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    for stat in stats {
+                        parent.spawn((
+                            TextBundle::from_section(stat, stat_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    }
+                    // Display buttons
+                    //  - retry
+                    //  - same seed again
+                    //  - main menu
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::New,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("play_again"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::PlaySeed,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("play_seed"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::MainMenu,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("main_menu"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
 }
 
-fn button_system(
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor, Option<&ToSelectOption>),
-        (Changed<Interaction>, With<Button>)
-    >,
+fn level_complete_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    campaign: Res<Campaign>,
+    play_time: Res<PlayTime>,
+    locale: Res<Locale>
 ) {
-    for (interaction, mut color, selected) in &mut interaction_query {
-        *color = match (*interaction, selected) {
-            (Interaction::Pressed, _) => PRESSED_BUTTON.into(),
-            (Interaction::None, Some(_)) => HOVERED_BUTTON.into(),
-            (Interaction::Hovered, Some(_)) => HOVERED_PRESSED_BUTTON.into(),
-            (Interaction::Hovered, None) => HOVERED_BUTTON.into(),
-            (Interaction::None, None) => NORMAL_BUTTON.into(),
-        }
-    }
-}
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(65. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(15. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    let stat_text_style = TextStyle {
+        font_size: 28.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
 
-fn menu_action(
-    interaction_query: Query<
-        (&Interaction, &MenuButtonAction),
-        (Changed<Interaction>, With<Button>)
-    >,
-    mut app_exit_events: EventWriter<AppExit>,
-    mut menu_state: ResMut<NextState<MenuState>>,
-    mut game_state: ResMut<NextState<GameState>>,
-) {
-    for (interaction, menut_button_action) in &interaction_query {
-        if *interaction == Interaction::Pressed {
-            match menut_button_action {
-                MenuButtonAction::Quit => app_exit_events.send(AppExit),
-                MenuButtonAction::New => {
-                    #[cfg(feature = "debug")]
-                    info!("menu act new");
-                    game_state.set(GameState::New);
-                    menu_state.set(MenuState::Play);
-                },
-                MenuButtonAction::Resume => {
-                    #[cfg(feature = "debug")]
-                    info!("menu act resume");
-                    game_state.set(GameState::Resume);
-                    menu_state.set(MenuState::Play);
-                },
-                MenuButtonAction::Pause => {
-                    #[cfg(feature = "debug")]
-                    info!("game pause");
-                    game_state.set(GameState::Menu);
-                    menu_state.set(MenuState::Pause);
-                }
-            }
-        }
+    let stage = campaign.stage.unwrap_or_default();
+    let last_stage = stage + 1 >= CAMPAIGN.len();
+    let mut stats = vec![locale.format("stage", &[("stage", &(stage + 1))])];
+    if let Some(goal) = campaign.goal {
+        stats.push(goal.label(&locale));
+    }
+    let secs = play_time.0.as_secs();
+    let time = format!("{:02}:{:02}", secs / 60, secs % 60);
+    stats.push(locale.format("time", &[("time", &time)]));
+    if last_stage {
+        stats.push(locale.get("all_cleared").to_string());
     }
-}
 
-fn menu_keys(
-    mut commands: Commands,
-    mut interactions: Query<
-        (Entity, &Transform, &mut BackgroundColor, Option<&ToSelectOption>), 
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnLevelCompleteScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: palette.panel.with_a(0.9).into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("stage_cleared"),
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(30. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    for stat in stats {
+                        parent.spawn((
+                            TextBundle::from_section(stat, stat_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    }
+                    // Display buttons
+                    //  - next stage, unless this was the last one
+                    //  - main menu
+                    if !last_stage {
+                        parent.spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: palette.hovered_button.into(),
+                                ..default()
+                            },
+                            ThemeRole::Button,
+                            MenuButtonAction::NextStage,
+                            MenuItems,
+                            ToSelectOption
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(locale.get("next_stage"), button_text_style.clone()),
+                                ThemeRole::Text
+                            ));
+                        });
+                    }
+                    let mut main_menu = parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: if last_stage {
+                                palette.hovered_button.into()
+                            } else {
+                                palette.button.into()
+                            },
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::MainMenu,
+                        MenuItems
+                    ));
+                    if last_stage {
+                        main_menu.insert(ToSelectOption);
+                    }
+                    main_menu.with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("main_menu"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+fn round_over_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    result: Res<RoundResult>,
+    locale: Res<Locale>
+) {
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(65. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(15. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    let stat_text_style = TextStyle {
+        font_size: 28.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+
+    let title = match result.winner {
+        Some(winner) => locale.format("winner", &[("player", &(winner + 1))]),
+        None => locale.get("draw").to_string(),
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnRoundOverScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: palette.panel.with_a(0.9).into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            title,
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(30. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    for (player, score) in result.scores.iter().enumerate() {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                locale.format("round_score", &[
+                                    ("player", &(player + 1)),
+                                    ("score", &score),
+                                ]),
+                                stat_text_style.clone()
+                            ),
+                            ThemeRole::Text
+                        ));
+                    }
+                    // Display buttons
+                    //  - rematch
+                    //  - main menu
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::New,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("rematch"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::MainMenu,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("main_menu"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+fn board_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    locale: Res<Locale>
+) {
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(44. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(4. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnBoardMenuScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: palette.panel.into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("board"),
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(10. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    // Display buttons
+                    //  - preset
+                    //  - columns
+                    //  - rows
+                    //  - turned in landscape
+                    //  - walls
+                    //  - level
+                    //  - computer snakes and how they play
+                    //  - seed
+                    //  - back
+                    for action in [
+                        MenuButtonAction::BoardPreset,
+                        MenuButtonAction::BoardCols,
+                        MenuButtonAction::BoardRows,
+                        MenuButtonAction::TurnLandscape,
+                        MenuButtonAction::Walls,
+                        MenuButtonAction::Level,
+                        MenuButtonAction::Bots,
+                        MenuButtonAction::BotStrategy,
+                        MenuButtonAction::Seed
+                    ] {
+                        parent.spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: palette.button.into(),
+                                ..default()
+                            },
+                            ThemeRole::Button,
+                            action,
+                            MenuItems
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    option_label(action, &game_settings, &locale),
+                                    button_text_style.clone()
+                                ),
+                                ThemeRole::Text,
+                                OptionText(action)
+                            ));
+                        });
+                    }
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Back,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("back"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+fn settings_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    settings_return: Res<SettingsReturn>,
+    locale: Res<Locale>
+) {
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(40. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(3. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 32.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnSettingsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: palette.panel.into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("settings"),
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(10. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    // Display buttons
+                    //  - speed and acceleration
+                    //  - smooth movement
+                    //  - board size, not while a game is paused
+                    //  - theme and grid
+                    //  - volumes and sound
+                    //  - language
+                    //  - controls
+                    //  - back
+                    let mut actions = vec![
+                        MenuButtonAction::Difficulty,
+                        MenuButtonAction::Accelerate,
+                        MenuButtonAction::Smooth,
+                    ];
+                    if settings_return.0 == MenuState::Main {
+                        actions.push(MenuButtonAction::BoardPreset);
+                    }
+                    actions.extend([
+                        MenuButtonAction::Theme,
+                        MenuButtonAction::Grid,
+                        MenuButtonAction::MasterVolume,
+                        MenuButtonAction::MusicVolume,
+                        MenuButtonAction::SfxVolume,
+                        MenuButtonAction::Mute,
+                        MenuButtonAction::Language,
+                    ]);
+                    for action in actions {
+                        parent.spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: palette.button.into(),
+                                ..default()
+                            },
+                            ThemeRole::Button,
+                            action,
+                            MenuItems
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    option_label(action, &game_settings, &locale),
+                                    button_text_style.clone()
+                                ),
+                                ThemeRole::Text,
+                                OptionText(action)
+                            ));
+                        });
+                    }
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Controls,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("controls"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Back,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("back"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+fn controls_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    keymap: Res<KeyMap>,
+    locale: Res<Locale>
+) {
+    let button_style = Style {
+        width: Val::Px(320. * game_settings.game_scale),
+        height: Val::Px(44. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(3. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 30.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    // one per player on a row
+    let half_button_style = Style {
+        width: Val::Px(157. * game_settings.game_scale),
+        ..button_style.clone()
+    };
+    let half_button_text_style = TextStyle {
+        font_size: 24.0 * game_settings.game_scale,
+        ..button_text_style.clone()
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnControlsScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: palette.panel.into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("controls"),
+                            TextStyle {
+                                font_size: 50.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(10. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    // Display buttons
+                    //  - one per action, rebinds it
+                    //  - gamepad of each player
+                    //  - reset to defaults
+                    //  - back
+                    // steering of both players side by side, then the
+                    // actions they share
+                    for row in 0..4 {
+                        parent
+                            .spawn(NodeBundle::default())
+                            .with_children(|parent| {
+                                for steering in Action::STEERING.iter() {
+                                    let action = steering[row].0;
+                                    parent.spawn((
+                                        ButtonBundle {
+                                            style: half_button_style.clone(),
+                                            background_color: palette.button.into(),
+                                            ..default()
+                                        },
+                                        ThemeRole::Button,
+                                        MenuButtonAction::Rebind(action),
+                                        MenuItems
+                                    ))
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            TextBundle::from_section(
+                                                keymap.label(action, &locale),
+                                                half_button_text_style.clone()
+                                            ),
+                                            ThemeRole::Text,
+                                            BindingText(action)
+                                        ));
+                                    });
+                                }
+                            });
+                    }
+                    for action in [Action::Pause, Action::Confirm, Action::Back] {
+                        parent.spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: palette.button.into(),
+                                ..default()
+                            },
+                            ThemeRole::Button,
+                            MenuButtonAction::Rebind(action),
+                            MenuItems
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn((
+                                TextBundle::from_section(
+                                    keymap.label(action, &locale),
+                                    button_text_style.clone()
+                                ),
+                                ThemeRole::Text,
+                                BindingText(action)
+                            ));
+                        });
+                    }
+                    parent
+                        .spawn(NodeBundle::default())
+                        .with_children(|parent| {
+                            for player in 0..2 {
+                                let action = MenuButtonAction::Gamepad(player);
+                                parent.spawn((
+                                    ButtonBundle {
+                                        style: half_button_style.clone(),
+                                        background_color: palette.button.into(),
+                                        ..default()
+                                    },
+                                    ThemeRole::Button,
+                                    action,
+                                    MenuItems
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn((
+                                        TextBundle::from_section(
+                                            option_label(action, &game_settings, &locale),
+                                            half_button_text_style.clone()
+                                        ),
+                                        ThemeRole::Text,
+                                        OptionText(action)
+                                    ));
+                                });
+                            }
+                        });
+                    parent.spawn((
+                        TextBundle::from_section(
+                            String::new(),
+                            TextStyle {
+                                font_size: 22.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        ),
+                        ThemeRole::Text,
+                        ControlsHint
+                    ));
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::ResetControls,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("reset_controls"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Settings,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("back"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+/// bind the next key pressed to the action waiting for one, unless another
+/// action read at the same time already uses it
+fn rebind_key(
+    mut kb_input: ResMut<Input<KeyCode>>,
+    mut keymap: ResMut<KeyMap>,
+    mut rebinding: ResMut<Rebinding>,
+    mut hints: Query<&mut Text, With<ControlsHint>>,
+    locale: Res<Locale>
+) {
+    let Some(action) = rebinding.0 else {
+        return;
+    };
+    let Some(key) = kb_input.get_just_pressed().next().copied() else {
+        return;
+    };
+    // the key is used up here, the menu must not react to it too
+    kb_input.clear_just_pressed(key);
+    rebinding.0 = None;
+
+    let hint = match keymap.conflict(action, key) {
+        Some(other) => locale.format("key_taken", &[
+            ("key", &format!("{key:?}")),
+            ("action", &other.label(&locale)),
+        ]),
+        None => {
+            keymap.bind(action, key);
+            keymap.save();
+            String::new()
+        }
+    };
+    if let Ok(mut text) = hints.get_single_mut() {
+        text.sections[0].value = hint;
+    }
+}
+
+fn stop_rebinding(mut rebinding: ResMut<Rebinding>) {
+    rebinding.0 = None;
+}
+
+fn binding_texts(
+    mut texts: Query<(&mut Text, &BindingText)>,
+    keymap: Res<KeyMap>,
+    rebinding: Res<Rebinding>,
+    locale: Res<Locale>
+) {
+    for (mut text, BindingText(action)) in texts.iter_mut() {
+        text.sections[0].value = if rebinding.0 == Some(*action) {
+            format!("{}: ...", action.label(&locale))
+        }
+        else {
+            keymap.label(*action, &locale)
+        };
+    }
+}
+
+fn high_score_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    high_scores: Res<HighScores>,
+    locale: Res<Locale>
+) {
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(65. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(20. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    let row_text_style = TextStyle {
+        font_size: 16.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnHighScoreScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::horizontal(
+                            Val::Px(10. * game_settings.game_scale)),
+                        ..default()
+                    },
+                    background_color: palette.panel.into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("high_scores"),
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(20. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    // rank, score, length, time, difficulty and date
+                    if high_scores.0.is_empty() {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("no_records"), row_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    }
+                    for (rank, entry) in high_scores.0.iter().enumerate() {
+                        parent.spawn((
+                            TextBundle::from_section(
+                                locale.format("high_score_row", &[
+                                    ("rank", &format!("{:>2}", rank + 1)),
+                                    ("score", &format!("{:>4}", entry.score)),
+                                    ("length", &format!("{:>3}", entry.length)),
+                                    ("time", &entry.duration_label()),
+                                    ("difficulty", &entry.difficulty.label(&locale)),
+                                    ("date", &entry.date_label()),
+                                ]),
+                                row_text_style.clone()
+                            )
+                            .with_style(
+                                Style { 
+                                    margin: UiRect::vertical(
+                                        Val::Px(3. * game_settings.game_scale)),
+                                    ..default()
+                                }
+                            ),
+                            ThemeRole::Text
+                        ));
+                    }
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Back,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("back"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+fn replays_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    replays: Res<Replays>,
+    locale: Res<Locale>
+) {
+    let button_style = Style {
+        width: Val::Px(250. * game_settings.game_scale),
+        height: Val::Px(50. * game_settings.game_scale),
+        margin: UiRect::all(Val::Px(5. * game_settings.game_scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+    let button_text_style = TextStyle {
+        font_size: 40.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    let hint_text_style = TextStyle {
+        font_size: 16.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                ..default()
+            },
+            ..default()
+            },
+            OnReplaysScreen,
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        padding: UiRect::horizontal(
+                            Val::Px(10. * game_settings.game_scale)),
+                        ..default()
+                    },
+                    background_color: palette.panel.into(),
+                    ..default()
+                    },
+                    ThemeRole::Panel,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            locale.get("replays"),
+                            TextStyle {
+                                font_size: 60.0 * game_settings.game_scale,
+                                color: palette.text,
+                                font: game_assets.cjk_font.clone(),
+                            },
+                        )
+                        .with_style(
+                            Style { 
+                                margin: UiRect::all(
+                                    Val::Px(20. * game_settings.game_scale)),
+                                ..default()
+                            }
+                        ),
+                        ThemeRole::Text
+                    ));
+                    // a row per replay, filled again after an import
+                    parent
+                        .spawn((NodeBundle {
+                            style: Style {
+                                flex_direction: FlexDirection::Column,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            ..default()
+                            },
+                            ReplayList
+                        ))
+                        .with_children(|parent| {
+                            spawn_replay_rows(parent, &replays, &game_assets, &game_settings,
+                                &palette, &locale);
+                        });
+                    parent.spawn((
+                        TextBundle::from_section(String::new(), hint_text_style)
+                            .with_style(Style {
+                                max_width: Val::Px(320. * game_settings.game_scale),
+                                margin: UiRect::vertical(
+                                    Val::Px(5. * game_settings.game_scale)),
+                                ..default()
+                            }),
+                        ThemeRole::Text,
+                        ReplaysHint
+                    ));
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style.clone(),
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::ImportReplays,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("import"), button_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                    parent.spawn((
+                        ButtonBundle {
+                            style: button_style,
+                            background_color: palette.hovered_button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        MenuButtonAction::Back,
+                        MenuItems,
+                        ToSelectOption
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(locale.get("back"), button_text_style),
+                            ThemeRole::Text
+                        ));
+                    });
+                });
+        });
+}
+
+/// a play and an export button per replay
+fn spawn_replay_rows(
+    parent: &mut ChildBuilder,
+    replays: &Replays,
+    game_assets: &GameAssets,
+    game_settings: &GameSetting,
+    palette: &Palette,
+    locale: &Locale
+) {
+    let scale = game_settings.game_scale;
+    let row_text_style = TextStyle {
+        font_size: 16.0 * scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    if replays.0.is_empty() {
+        parent.spawn((
+            TextBundle::from_section(locale.get("no_records"), row_text_style.clone()),
+            ThemeRole::Text
+        ));
+    }
+    for (index, replay) in replays.0.iter().enumerate() {
+        parent
+            .spawn(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::Row,
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for (action, label, width) in [
+                    (MenuButtonAction::PlayReplay(index), replay.label(locale), 250.),
+                    (MenuButtonAction::ExportReplay(index), locale.get("export").to_string(), 60.),
+                ] {
+                    parent.spawn((
+                        ButtonBundle {
+                            style: Style {
+                                width: Val::Px(width * scale),
+                                height: Val::Px(34. * scale),
+                                margin: UiRect::all(Val::Px(3. * scale)),
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                ..default()
+                            },
+                            background_color: palette.button.into(),
+                            ..default()
+                        },
+                        ThemeRole::Button,
+                        action,
+                        MenuItems
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn((
+                            TextBundle::from_section(label, row_text_style.clone()),
+                            ThemeRole::Text
+                        ));
+                    });
+                }
+            });
+    }
+}
+
+/// list the replays again once an import added some
+fn replay_rows(
+    mut commands: Commands,
+    list_q: Query<Entity, With<ReplayList>>,
+    replays: Res<Replays>,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    locale: Res<Locale>
+) {
+    for list in list_q.iter() {
+        commands
+            .entity(list)
+            .despawn_descendants()
+            .with_children(|parent| {
+                spawn_replay_rows(parent, &replays, &game_assets, &game_settings, &palette,
+                    &locale);
+            });
+    }
+}
+
+pub fn play_menu_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    game_mode: Res<GameMode>,
+    demo: Res<Demo>,
+    playback: Option<Res<Playback>>,
+    locale: Res<Locale>
+) {
+    // the demo plays without it
+    let visibility = if demo.playing { Visibility::Hidden } else { Visibility::Inherited };
+    spawn_play_bar(
+        &mut commands,
+        &game_assets,
+        &game_settings,
+        &palette,
+        &locale,
+        *game_mode,
+        visibility,
+        playback.is_some(),
+        [0, 0]
+    );
+}
+
+/// the play bar laid out again when the window turns or the language
+/// changes, with the scores so far and shown or hidden as it was
+fn relayout_play_bar(
+    mut commands: Commands,
+    bar_q: Query<(Entity, &Visibility), With<OnPlayMenuScreen>>,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    game_mode: Res<GameMode>,
+    sim: Res<Simulation>,
+    playback: Option<Res<Playback>>,
+    locale: Res<Locale>,
+    mut shown: Local<(Layout, Language)>
+) {
+    if *shown == (game_settings.layout, locale.language) {
+        return;
+    }
+    *shown = (game_settings.layout, locale.language);
+    let Ok((bar, visibility)) = bar_q.get_single() else {
+        return;
+    };
+    commands.entity(bar).despawn_recursive();
+    let score = |index: usize| sim.0.snakes()
+        .get(index)
+        .map(|snake| snake.score())
+        .unwrap_or(0);
+    spawn_play_bar(
+        &mut commands,
+        &game_assets,
+        &game_settings,
+        &palette,
+        &locale,
+        *game_mode,
+        *visibility,
+        playback.is_some(),
+        [score(0), score(1)]
+    );
+}
+
+/// pause button, campaign goal and scores, above the board or on its left
+/// in a landscape window
+fn spawn_play_bar(
+    commands: &mut Commands,
+    game_assets: &GameAssets,
+    game_settings: &GameSetting,
+    palette: &Palette,
+    locale: &Locale,
+    game_mode: GameMode,
+    visibility: Visibility,
+    playback: bool,
+    scores: [u32; 2]
+) {
+    let button_text_style = TextStyle {
+        font_size: 25.0 * game_settings.game_scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    let landscape = game_settings.layout == Layout::Landscape;
+
+    let bar_style = if landscape {
+        Style {
+            flex_direction: FlexDirection::Column,
+            align_items: AlignItems::Start,
+            width: Val::Px(HUD_WIDTH * game_settings.game_scale),
+            height: Val::Percent(100.0),
+            padding: UiRect::all(Val::Px(5. * game_settings.game_scale)),
+            ..default()
+        }
+    } else {
+        Style {
+            display: Display::Grid,
+            width: Val::Percent(100.0),
+            height: Val::Px(BOARD_OFFSET_Y * game_settings.game_scale),
+            grid_template_columns: vec![
+                GridTrack::percent(30.), 
+                GridTrack::percent(30.),
+                GridTrack::auto()
+            ],
+            ..default()
+        }
+    };
+    commands
+        .spawn((NodeBundle {
+            style: bar_style,
+            visibility,
+            ..default()
+            },
+            OnPlayMenuScreen,
+            OnGameScreen,
+        )) 
+        .with_children(|parent| {
+            // pause button
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        width: Val::Px(50. * game_settings.game_scale),
+                        height: if landscape {
+                            Val::Px(30. * game_settings.game_scale)
+                        } else {
+                            Val::Auto
+                        },
+                        margin: UiRect::all(Val::Px(5. * game_settings.game_scale)),
+                        grid_column: GridPlacement::start(1),
+                        justify_content: JustifyContent::Center,
+                        ..default()
+                    },
+                    background_color: palette.button.into(),
+                    // a playback has its own controls
+                    visibility: if playback { Visibility::Hidden } else { Visibility::Inherited },
+                    ..default()
+                },
+                ThemeRole::Button,
+                MenuButtonAction::Pause,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section(
+                        locale.get("pause"),
+                        button_text_style.clone()
+                    ),
+                    ThemeRole::Text
+                ));
+            });
+
+            let column_style = Style {
+                grid_column: GridPlacement::start(2),
+                align_self: if landscape { AlignSelf::Start } else { AlignSelf::Center },
+                margin: UiRect::all(Val::Px(3. * game_settings.game_scale)),
+                ..default()
+            };
+            let score_style = Style {
+                grid_column: GridPlacement::start(3),
+                justify_self: JustifySelf::End,
+                ..column_style.clone()
+            };
+            match game_mode {
+                GameMode::Solo => {
+                    // campaign goal, filled in by control::check_goal
+                    parent.spawn((TextBundle::from_section(
+                            String::new(),
+                            button_text_style.clone()
+                        ).with_style(column_style),
+                        ThemeRole::Text,
+                        GoalText
+                    ));
+
+                    // score board
+                    parent.spawn((TextBundle::from_section(
+                            score_label(0, scores[0], game_mode, locale),
+                            button_text_style.clone()
+                        ).with_style(score_style),
+                        ThemeRole::Text,
+                        Score(0)
+                    ));
+                },
+                GameMode::Versus => {
+                    // a score board per player
+                    parent.spawn((TextBundle::from_section(
+                            score_label(0, scores[0], game_mode, locale),
+                            button_text_style.clone()
+                        ).with_style(column_style),
+                        ThemeRole::Text,
+                        Score(0)
+                    ));
+                    parent.spawn((TextBundle::from_section(
+                            score_label(1, scores[1], game_mode, locale),
+                            button_text_style.clone()
+                        ).with_style(score_style),
+                        ThemeRole::Text,
+                        Score(1)
+                    ));
+                }
+            }
+        }); 
+}
+
+fn button_system(
+    mut interaction_query: Query<
+        (&Interaction, &mut BackgroundColor, Option<&ToSelectOption>),
+        (Changed<Interaction>, With<Button>)
+    >,
+    palette: Res<Palette>
+) {
+    for (interaction, mut color, selected) in &mut interaction_query {
+        *color = palette.button_color(*interaction, selected.is_some()).into();
+    }
+}
+
+fn menu_action(
+    interaction_query: Query<
+        (&Interaction, &MenuButtonAction),
+        (Changed<Interaction>, With<Button>)
+    >,
+    mut menu_action_evw: EventWriter<MenuActionEvent>,
+) {
+    for (interaction, menut_button_action) in &interaction_query {
+        if *interaction == Interaction::Pressed {
+            menu_action_evw.send(MenuActionEvent(*menut_button_action));
+        }
+    }
+}
+
+fn handle_menu_action(
+    mut commands: Commands,
+    mut menu_action_evr: EventReader<MenuActionEvent>,
+    mut app_exit_events: EventWriter<AppExit>,
+    mut menu_state: ResMut<NextState<MenuState>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut game_settings: ResMut<GameSetting>,
+    mut campaign: ResMut<Campaign>,
+    mut game_mode: ResMut<GameMode>,
+    rng: Res<GameRng>,
+    progress: Res<CampaignProgress>,
+    mut keymap: ResMut<KeyMap>,
+    mut rebinding: ResMut<Rebinding>,
+    mut hints: Query<&mut Text, Or<(With<ControlsHint>, With<ReplaysHint>)>>,
+    gamepads: Res<Gamepads>,
+    mut replays: ResMut<Replays>,
+    // one parameter, a system takes at most 16
+    (curr_menu_state, mut settings_return, locale): (
+        Res<State<MenuState>>,
+        ResMut<SettingsReturn>,
+        Res<Locale>
+    ),
+) {
+    for MenuActionEvent(action) in menu_action_evr.read() {
+        // kept for the next session when the player changed a setting, not
+        // when a game or a replay brought its own
+        let preferences = game_settings.preferences();
+        match action {
+            MenuButtonAction::Quit => app_exit_events.send(AppExit),
+            MenuButtonAction::New => {
+                #[cfg(feature = "debug")]
+                info!("menu act new");
+                game_state.set(GameState::New);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::Continue => {
+                game_state.set(GameState::Continue);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::Resume => {
+                #[cfg(feature = "debug")]
+                info!("menu act resume");
+                game_state.set(GameState::Resume);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::Pause => {
+                #[cfg(feature = "debug")]
+                info!("game pause");
+                game_state.set(GameState::Menu);
+                menu_state.set(MenuState::Pause);
+            },
+            // the pause menu's settings change the game and the player's own
+            MenuButtonAction::Difficulty => {
+                let difficulty = game_settings.difficulty.next();
+                game_settings.choose(|settings| settings.difficulty = difficulty);
+            },
+            MenuButtonAction::Accelerate => {
+                let accelerate = !game_settings.accelerate;
+                game_settings.choose(|settings| settings.accelerate = accelerate);
+            },
+            MenuButtonAction::Smooth => {
+                game_settings.smooth = !game_settings.smooth;
+            },
+            MenuButtonAction::Theme => {
+                game_settings.theme = (game_settings.theme + 1) % THEMES.len();
+            },
+            MenuButtonAction::HighScores => {
+                menu_state.set(MenuState::HighScores);
+            },
+            MenuButtonAction::Back => {
+                // the settings go back to the menu they were opened from
+                match curr_menu_state.get() {
+                    MenuState::Settings => menu_state.set(settings_return.0),
+                    _ => menu_state.set(MenuState::Main),
+                }
+            },
+            MenuButtonAction::Settings => {
+                // the controls screen comes back to the settings
+                if curr_menu_state.get() != &MenuState::Controls {
+                    settings_return.0 = *curr_menu_state.get();
+                }
+                menu_state.set(MenuState::Settings);
+            },
+            MenuButtonAction::Grid => {
+                game_settings.grid = !game_settings.grid;
+            },
+            MenuButtonAction::Language => {
+                game_settings.language = game_settings.language.next();
+            },
+            MenuButtonAction::MasterVolume => {
+                game_settings.master_volume = next_volume(game_settings.master_volume);
+            },
+            MenuButtonAction::MusicVolume => {
+                game_settings.music_volume = next_volume(game_settings.music_volume);
+            },
+            MenuButtonAction::SfxVolume => {
+                game_settings.sfx_volume = next_volume(game_settings.sfx_volume);
+            },
+            MenuButtonAction::Mute => {
+                game_settings.muted = !game_settings.muted;
+            },
+            MenuButtonAction::MainMenu => {
+                game_state.set(GameState::Menu);
+                menu_state.set(MenuState::Main);
+            },
+            MenuButtonAction::Board => {
+                menu_state.set(MenuState::Board);
+            },
+            MenuButtonAction::BoardPreset => {
+                // next preset after the current one, the first if custom
+                let board = game_settings.board;
+                let next = BOARD_PRESETS
+                    .iter()
+                    .position(|(_, preset)| *preset == board)
+                    .map(|i| (i + 1) % BOARD_PRESETS.len())
+                    .unwrap_or(0);
+                game_settings.board = BOARD_PRESETS[next].1;
+            },
+            MenuButtonAction::BoardCols => {
+                let cols = game_settings.board.cols;
+                game_settings.board.cols =
+                    if cols >= BOARD_MAX_COLS { BOARD_MIN_SIDE } else { cols + 1 };
+            },
+            MenuButtonAction::BoardRows => {
+                let rows = game_settings.board.rows;
+                game_settings.board.rows =
+                    if rows >= BOARD_MAX_ROWS { BOARD_MIN_SIDE } else { rows + 1 };
+            },
+            MenuButtonAction::TurnLandscape => {
+                game_settings.turn_landscape = !game_settings.turn_landscape;
+            },
+            MenuButtonAction::Walls => {
+                game_settings.walls = match game_settings.walls {
+                    Walls::Solid => Walls::Portal,
+                    Walls::Portal => Walls::Solid,
+                };
+            },
+            MenuButtonAction::Level => {
+                // empty board, then every level in turn
+                game_settings.level = match game_settings.level {
+                    None => Some(0),
+                    Some(i) if i + 1 < LEVELS.len() => Some(i + 1),
+                    Some(_) => None,
+                };
+            },
+            MenuButtonAction::Bots => {
+                game_settings.bots = (game_settings.bots + 1) % (BOTS_MAX + 1);
+            },
+            MenuButtonAction::BotStrategy => {
+                game_settings.bot_strategy = game_settings.bot_strategy.next();
+            },
+            MenuButtonAction::Seed => {
+                // random, then today's, then the last game's if another
+                let daily = GameRng::daily_seed();
+                game_settings.seed = match game_settings.seed {
+                    None => Some(daily),
+                    Some(seed) if seed == daily && rng.seed != daily => Some(rng.seed),
+                    Some(_) => None,
+                };
+            },
+            MenuButtonAction::PlaySeed => {
+                game_settings.seed = Some(rng.seed);
+                game_state.set(GameState::New);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::Campaign => {
+                *game_mode = GameMode::Solo;
+                campaign.stage = Some(progress.next_stage());
+                game_state.set(GameState::New);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::Versus => {
+                *game_mode = GameMode::Versus;
+                game_state.set(GameState::New);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::NextStage => {
+                campaign.stage = campaign.stage
+                    .map(|stage| (stage + 1).min(CAMPAIGN.len() - 1));
+                game_state.set(GameState::New);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::Controls => {
+                menu_state.set(MenuState::Controls);
+            },
+            MenuButtonAction::Rebind(action) => {
+                rebinding.0 = Some(*action);
+                if let Ok(mut text) = hints.get_single_mut() {
+                    text.sections[0].value = locale.get("press_new_key").to_string();
+                }
+            },
+            MenuButtonAction::ResetControls => {
+                *keymap = KeyMap::default();
+                keymap.save();
+                if let Ok(mut text) = hints.get_single_mut() {
+                    text.sections[0].value = locale.get("controls_reset").to_string();
+                }
+            },
+            MenuButtonAction::Replays => {
+                menu_state.set(MenuState::Replays);
+            },
+            MenuButtonAction::PlayReplay(index) => {
+                let Some(replay) = replays.0.get(*index).cloned() else {
+                    continue;
+                };
+                info!("playing back the replay of seed {}", replay.seed);
+                // the player's settings come back once the playback is left
+                let settings = replay.settings(&game_settings);
+                game_settings.play_with(settings);
+                campaign.stage = replay.stage;
+                *game_mode = replay.game_mode();
+                commands.insert_resource(Playback::new(replay));
+                game_state.set(GameState::New);
+                menu_state.set(MenuState::Play);
+            },
+            MenuButtonAction::ExportReplay(index) => {
+                let hint = match replays.export(*index) {
+                    Ok(path) => locale.format("exported", &[("path", &path)]),
+                    Err(err) => locale.format("export_failed", &[("error", &err)]),
+                };
+                if let Ok(mut text) = hints.get_single_mut() {
+                    text.sections[0].value = hint;
+                }
+            },
+            MenuButtonAction::ImportReplays => {
+                let hint = match replays.import() {
+                    Ok(count) => {
+                        replays.save();
+                        locale.format("imported", &[("count", &count)])
+                    },
+                    Err(err) => locale.format("import_failed", &[("error", &err)]),
+                };
+                if let Ok(mut text) = hints.get_single_mut() {
+                    text.sections[0].value = hint;
+                }
+            },
+            MenuButtonAction::Gamepad(player) => {
+                // any gamepad, then every connected one in turn
+                let mut connected = gamepads.iter().collect::<Vec<_>>();
+                connected.sort_by_key(|gamepad| gamepad.id);
+                game_settings.gamepads[*player] = match game_settings.gamepads[*player] {
+                    None => connected.first().copied(),
+                    Some(current) => connected
+                        .iter()
+                        .position(|gamepad| *gamepad == current)
+                        .and_then(|i| connected.get(i + 1))
+                        .copied()
+                };
+            }
+        }
+        if game_settings.preferences() != preferences {
+            game_settings.save();
+        }
+    }
+}
+
+/// text shown on the button of a setting
+fn option_label(action: MenuButtonAction, game_settings: &GameSetting, locale: &Locale) -> String {
+    let on_off = |on: bool| locale.get(if on { "on" } else { "off" });
+    let option = |key: &str, value: &dyn std::fmt::Display| locale.format(key, &[("value", value)]);
+    match action {
+        MenuButtonAction::Difficulty =>
+            option("option_difficulty", &game_settings.difficulty.label(locale)),
+        MenuButtonAction::Accelerate =>
+            option("option_accelerate", &on_off(game_settings.accelerate)),
+        MenuButtonAction::Mute =>
+            option("option_sound", &on_off(!game_settings.muted)),
+        MenuButtonAction::Smooth => option("option_movement",
+            &locale.get(if game_settings.smooth { "movement_smooth" } else { "movement_cells" })),
+        MenuButtonAction::Theme =>
+            option("option_theme", &locale.get(THEMES[game_settings.theme].0)),
+        MenuButtonAction::Grid =>
+            option("option_grid", &on_off(game_settings.grid)),
+        MenuButtonAction::MasterVolume =>
+            option("option_master_volume", &percent(game_settings.master_volume)),
+        MenuButtonAction::MusicVolume =>
+            option("option_music_volume", &percent(game_settings.music_volume)),
+        MenuButtonAction::SfxVolume =>
+            option("option_sfx_volume", &percent(game_settings.sfx_volume)),
+        MenuButtonAction::Language =>
+            option("option_language", &game_settings.language.label()),
+        MenuButtonAction::BoardPreset => {
+            let board = game_settings.board;
+            let name = BOARD_PRESETS
+                .iter()
+                .find(|(_, preset)| *preset == board)
+                .map(|(name, _)| *name)
+                .unwrap_or("custom");
+            format!("{} {}x{}", locale.get(name), board.cols, board.rows)
+        },
+        MenuButtonAction::BoardCols =>
+            option("option_cols", &game_settings.board.cols),
+        MenuButtonAction::BoardRows =>
+            option("option_rows", &game_settings.board.rows),
+        MenuButtonAction::TurnLandscape => option("option_turn_landscape",
+            &locale.get(if game_settings.turn_landscape { "turn" } else { "no_turn" })),
+        MenuButtonAction::Walls => locale.get(match game_settings.walls {
+            Walls::Solid => "walls_solid",
+            Walls::Portal => "walls_portal",
+        }).to_string(),
+        MenuButtonAction::Level => option("option_level", &locale.get(game_settings.level
+            .and_then(|i| LEVELS.get(i))
+            .map(|(name, _)| *name)
+            .unwrap_or("none"))),
+        MenuButtonAction::Seed => match game_settings.seed {
+            None => locale.get("seed_random").to_string(),
+            Some(seed) if seed == GameRng::daily_seed() => locale.get("seed_daily").to_string(),
+            Some(seed) => locale.format("seed", &[("seed", &seed)]),
+        },
+        MenuButtonAction::Bots => option("option_bots", &game_settings.bots),
+        MenuButtonAction::BotStrategy =>
+            option("option_bot_strategy", &locale.get(game_settings.bot_strategy.label_key())),
+        MenuButtonAction::Gamepad(player) => {
+            let value = match game_settings.gamepads[player] {
+                Some(gamepad) => locale.format("gamepad_id", &[("id", &(gamepad.id + 1))]),
+                None => locale.get("gamepad_any").to_string(),
+            };
+            locale.format("option_gamepad", &[("player", &(player + 1)), ("value", &value)])
+        },
+        _ => String::new()
+    }
+}
+
+/// switch to the chosen language once its strings are loaded
+fn apply_locale(
+    game_settings: Res<GameSetting>,
+    game_assets: Res<GameAssets>,
+    tables: Res<Assets<Strings>>,
+    mut locale: ResMut<Locale>
+) {
+    let language = game_settings.language;
+    if locale.language == language {
+        return;
+    }
+    let Some(strings) = Language::ALL
+        .iter()
+        .position(|l| *l == language)
+        .and_then(|i| game_assets.locales.get(i))
+        .and_then(|handle| tables.get(handle)) else {
+        return;
+    };
+    locale.set(language, strings.clone());
+}
+
+/// the open menu built again in the new language, the play bar follows
+/// in relayout_play_bar
+fn relocalize_menu(world: &mut World, mut shown: Local<Language>) {
+    let language = world.resource::<Locale>().language;
+    if *shown == language {
+        return;
+    }
+    *shown = language;
+    let menu = *world.resource::<State<MenuState>>().get();
+    if menu == MenuState::Play {
+        return;
+    }
+    // a same state transition runs neither, so the schedules are run here
+    let _ = world.try_run_schedule(OnExit(menu));
+    let _ = world.try_run_schedule(OnEnter(menu));
+}
+
+fn retitle_window(mut windows: Query<&mut Window>, locale: Res<Locale>) {
+    for mut window in windows.iter_mut() {
+        window.title = locale.get("title").to_string();
+    }
+}
+
+/// a volume a tenth louder, back to silent past the loudest
+fn next_volume(volume: f32) -> f32 {
+    let tenths = (volume * 10.).round() + 1.;
+    if tenths > 10. { 0. } else { tenths / 10. }
+}
+
+fn percent(volume: f32) -> u32 {
+    (volume * 100.).round() as u32
+}
+
+fn option_texts(
+    mut texts: Query<(&mut Text, &OptionText)>,
+    game_settings: Res<GameSetting>,
+    locale: Res<Locale>
+) {
+    for (mut text, OptionText(action)) in texts.iter_mut() {
+        text.sections[0].value = option_label(*action, &game_settings, &locale);
+    }
+}
+
+fn menu_keys(
+    mut commands: Commands,
+    mut interactions: Query<
+        (Entity, &Transform, &mut BackgroundColor, Option<&ToSelectOption>), 
         With<MenuItems>
     >,
     kb_input: Res<Input<KeyCode>>,
+    keymap: Res<KeyMap>,
+    rebinding: Res<Rebinding>,
+    steer: Res<GamepadSteer>,
+    buttons: Res<Input<GamepadButton>>,
     menu_actions: Query<
         (&MenuButtonAction, Option<&ToSelectOption>),
         With<Button>
     >,
-    mut app_exit_events: EventWriter<AppExit>,
+    mut menu_action_evw: EventWriter<MenuActionEvent>,
     mut menu_state: ResMut<NextState<MenuState>>,
     mut game_state: ResMut<NextState<GameState>>,
-    curr_menu_state: Res<State<MenuState>>
+    curr_menu_state: Res<State<MenuState>>,
+    settings_return: Res<SettingsReturn>,
+    palette: Res<Palette>
 ) {
+    // the controls screen is waiting for a key
+    if rebinding.0.is_some() {
+        return;
+    }
+    // any gamepad drives the menus, A confirms and B goes back
+    let steered = steer.direction(None).or(keymap.direction(None, &kb_input));
+    let down = steered == Some(DOWN);
+    let up = steered == Some(UP);
+    let confirm = keymap.just_pressed(Action::Confirm, &kb_input) ||
+        gamepad_just_pressed(&buttons, GamepadButtonType::South);
+    let back = keymap.just_pressed(Action::Back, &kb_input) ||
+        gamepad_just_pressed(&buttons, GamepadButtonType::East);
+
     let mut interactions_vec = interactions.iter_mut().collect::<Vec<_>>();
-    interactions_vec.sort_by_key(|c| c.1.translation.y as i32);
-    if kb_input.any_just_pressed([KeyCode::Down, KeyCode::S, KeyCode::J]) {
+    // top to bottom, left to right on a row
+    interactions_vec.sort_by_key(|c| (c.1.translation.y as i32, c.1.translation.x as i32));
+    if down {
         let mut focused = interactions_vec.iter_mut();
-        while let Some((entity, _trans, bgcolor, maybe_select)) = 
-            focused.next() {
+        for (entity, _trans, bgcolor, maybe_select) in focused.by_ref() {
             if maybe_select.is_some() {
                 commands.entity(*entity).remove::<ToSelectOption>();
-                **bgcolor = NORMAL_BUTTON.into();
+                **bgcolor = palette.button.into();
                 break;
             }
         }
         if let Some((entity, _trans, bgcolor, _maybe_select)) =
             focused.next() {
             commands.entity(*entity).insert(ToSelectOption);
-            **bgcolor = HOVERED_BUTTON.into();
+            **bgcolor = palette.hovered_button.into();
         }
-        else {
-            if let Some(first) = interactions_vec.iter_mut().next() {
-                commands.entity((*first).0).insert(ToSelectOption);
-                *(*first).2 = HOVERED_BUTTON.into();
-            }
+        else if let Some(first) = interactions_vec.iter_mut().next() {
+            commands.entity(first.0).insert(ToSelectOption);
+            *first.2 = palette.hovered_button.into();
         }
     }
 
-    if kb_input.any_just_pressed([KeyCode::Up, KeyCode::W, KeyCode::K]) {
+    if up {
         let mut focused = interactions_vec.iter_mut().rev();
-        while let Some((entity, _trans, bgcolor, maybe_select)) = 
-            focused.next() {
+        for (entity, _trans, bgcolor, maybe_select) in focused.by_ref() {
             if maybe_select.is_some() {
                 commands.entity(*entity).remove::<ToSelectOption>();
-                **bgcolor = NORMAL_BUTTON.into();
+                **bgcolor = palette.button.into();
                 break;
             }
         }
         if let Some((entity, _trans, bgcolor, _maybe_select)) =
             focused.next() {
             commands.entity(*entity).insert(ToSelectOption);
-            **bgcolor = HOVERED_BUTTON.into();
+            **bgcolor = palette.hovered_button.into();
         }
-        else {
-            if let Some(first) = interactions_vec.iter_mut().rev().next() {
-                commands.entity((*first).0).insert(ToSelectOption);
-                *(*first).2 = HOVERED_BUTTON.into();
-            }
+        else if let Some(last) = interactions_vec.iter_mut().next_back() {
+            commands.entity(last.0).insert(ToSelectOption);
+            *last.2 = palette.hovered_button.into();
         }
     }
 
-    if kb_input.any_just_pressed([KeyCode::Return, KeyCode::Space]) {
+    if confirm {
         for (menu_action, maybe_select) in menu_actions.iter() {
             if maybe_select.is_some() {
-                match menu_action {
-                    MenuButtonAction::Quit => app_exit_events.send(AppExit),
-                    MenuButtonAction::New => {
-                        #[cfg(feature = "debug")]
-                        info!("keyboard new");
-                        game_state.set(GameState::New);
-                        menu_state.set(MenuState::Play);
-                    },
-                    MenuButtonAction::Resume => {
-                        #[cfg(feature = "debug")]
-                        info!("keyboard resume");
-                        game_state.set(GameState::Resume);
-                        menu_state.set(MenuState::Play);
-                    },
-                    MenuButtonAction::Pause => {
-                        #[cfg(feature = "debug")]
-                        info!("keyboard pause");
-                        game_state.set(GameState::Menu);
-                        menu_state.set(MenuState::Pause);
-                    }
-                }
+                menu_action_evw.send(MenuActionEvent(*menu_action));
             }
         }
     }
 
     // resume game directly
-    if curr_menu_state.get() == &MenuState::Pause {
-        if kb_input.just_pressed(KeyCode::Escape) {
-            #[cfg(feature = "debug")]
-            info!("keyboard resume");
-            game_state.set(GameState::Resume);
-            menu_state.set(MenuState::Play);
+    if curr_menu_state.get() == &MenuState::Pause && back {
+        #[cfg(feature = "debug")]
+        info!("keyboard resume");
+        game_state.set(GameState::Resume);
+        menu_state.set(MenuState::Play);
+    }
+
+    // leave the high score table, the board settings or the replays
+    if matches!(curr_menu_state.get(),
+        MenuState::HighScores | MenuState::Board | MenuState::Replays) && back {
+        menu_state.set(MenuState::Main);
+    }
+
+    // the settings go back where they were opened from, the controls to
+    // the settings
+    if back {
+        match curr_menu_state.get() {
+            MenuState::Settings => menu_state.set(settings_return.0),
+            MenuState::Controls => menu_state.set(MenuState::Settings),
+            _ => {}
         }
     }
+
+    // leave the game over, the level complete or the round over screen
+    if matches!(curr_menu_state.get(),
+        MenuState::GameOver | MenuState::LevelComplete | MenuState::RoundOver) && back {
+        game_state.set(GameState::Menu);
+        menu_state.set(MenuState::Main);
+    }
 }
@@ -0,0 +1,370 @@
+//! Recorded games: the seed, the settings and the move of every snake on
+//! every tick, enough to play a game again through the same systems. The
+//! latest replays are kept with `storage` and can be exported to files and
+//! imported back.
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{
+    resources::{GameSetting, GameMode, Campaign, Difficulty},
+    sim::{Board, Walls, UP, DOWN, LEFT, RIGHT},
+    locale::Locale,
+    storage
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    /// unix seconds of the start
+    pub date: u64,
+    pub seed: u64,
+    pub cols: u32,
+    pub rows: u32,
+    pub portal: bool,
+    /// index in `level::LEVELS`
+    pub level: Option<usize>,
+    /// campaign stage, it decides the level
+    pub stage: Option<usize>,
+    pub versus: bool,
+    pub bots: usize,
+    pub difficulty: Difficulty,
+    pub accelerate: bool,
+    /// played on a board turned for a landscape window
+    #[serde(default)]
+    pub turned: bool,
+    /// the board turned a quarter with the window once this many ticks
+    /// were played, clockwise if it was upright
+    #[serde(default)]
+    pub turns: Vec<usize>,
+    /// final score of the first snake
+    pub score: u32,
+    /// a line per tick with a letter per snake: U, D, L, R, or . holding
+    /// still
+    pub ticks: Vec<String>,
+}
+
+impl Replay {
+    pub fn new(
+        seed: u64,
+        game_settings: &GameSetting,
+        campaign: &Campaign,
+        game_mode: GameMode
+    ) -> Self {
+        Self {
+            date: storage::unix_now(),
+            seed,
+            cols: game_settings.board.cols,
+            rows: game_settings.board.rows,
+            portal: game_settings.walls == Walls::Portal,
+            level: game_settings.level,
+            stage: campaign.stage,
+            versus: game_mode == GameMode::Versus,
+            bots: game_settings.bots,
+            difficulty: game_settings.difficulty,
+            accelerate: game_settings.accelerate,
+            turned: game_settings.turned,
+            turns: Vec::new(),
+            score: 0,
+            ticks: Vec::new(),
+        }
+    }
+
+    /// record the directions given to the snakes on one tick
+    pub fn push(&mut self, directions: &[IVec2]) {
+        self.ticks.push(directions
+            .iter()
+            .map(|direction| match *direction {
+                UP => 'U',
+                DOWN => 'D',
+                LEFT => 'L',
+                RIGHT => 'R',
+                _ => '.',
+            })
+            .collect());
+    }
+
+    /// the board turned before the next tick
+    pub fn turn(&mut self) {
+        self.turns.push(self.ticks.len());
+    }
+
+    /// the directions of the snakes on `tick`, holding still past the end
+    pub fn directions(&self, tick: usize) -> Vec<IVec2> {
+        self.ticks
+            .get(tick)
+            .map(|line| line
+                .chars()
+                .map(|c| match c {
+                    'U' => UP,
+                    'D' => DOWN,
+                    'L' => LEFT,
+                    'R' => RIGHT,
+                    _ => IVec2::ZERO,
+                })
+                .collect())
+            .unwrap_or_default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        if self.versus { GameMode::Versus } else { GameMode::Solo }
+    }
+
+    /// `game_settings` changed to what the replay was played with
+    pub fn settings(&self, game_settings: &GameSetting) -> GameSetting {
+        GameSetting {
+            board: Board::new(self.cols, self.rows),
+            walls: if self.portal { Walls::Portal } else { Walls::Solid },
+            level: self.level,
+            bots: self.bots,
+            difficulty: self.difficulty,
+            accelerate: self.accelerate,
+            turned: self.turned,
+            seed: Some(self.seed),
+            ..game_settings.clone()
+        }
+    }
+
+    /// date, mode and score for the replays screen
+    pub fn label(&self, locale: &Locale) -> String {
+        let mode = match (self.versus, self.stage) {
+            (true, _) => locale.get("mode_versus").to_string(),
+            (false, Some(stage)) => locale.format("stage", &[("stage", &(stage + 1))]),
+            (false, None) => locale.get("mode_solo").to_string(),
+        };
+        locale.format("replay_row", &[
+            ("date", &storage::date_label(self.date)),
+            ("mode", &mode),
+            ("score", &self.score),
+        ])
+    }
+
+    /// name of the exported file
+    pub fn file_name(&self) -> String {
+        format!("replay-{}-{}", self.date, self.seed)
+    }
+}
+
+/// latest finished games, newest first, persisted across sessions
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+pub struct Replays(pub Vec<Replay>);
+
+impl Replays {
+    const KEY: &'static str = "replays";
+    /// folder of the exported and imported files
+    const DIR: &'static str = "replays";
+    pub const MAX: usize = 8;
+
+    pub fn load() -> Self {
+        storage::load(Self::KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::KEY, self);
+    }
+
+    pub fn add(&mut self, replay: Replay) {
+        self.0.insert(0, replay);
+        self.0.truncate(Self::MAX);
+    }
+
+    /// write replay `index` to its own file, the path written to
+    pub fn export(&self, index: usize) -> Result<String, String> {
+        let replay = self.0.get(index).ok_or("no such replay")?;
+        storage::export(Self::DIR, &replay.file_name(), replay)
+    }
+
+    /// add the replay files not kept yet, how many were added
+    pub fn import(&mut self) -> Result<usize, String> {
+        let mut imported: Vec<Replay> = storage::import(Self::DIR)?;
+        imported.retain(|replay| !self.0.contains(replay));
+        imported.sort_by_key(|replay| replay.date);
+        let count = imported.len().min(Self::MAX);
+        for replay in imported {
+            self.add(replay);
+        }
+        Ok(count)
+    }
+}
+
+/// the game being recorded, none in the demo or a playback
+#[derive(Debug, Default, Resource)]
+pub struct Recording(pub Option<Replay>);
+
+/// a replay playing back, only there while it does
+#[derive(Resource)]
+pub struct Playback {
+    pub replay: Replay,
+    /// ticks played so far
+    pub tick: usize,
+    pub paused: bool,
+    /// play one tick while paused
+    pub step: bool,
+    /// index in `SPEEDS`
+    pub speed: usize,
+    /// tick to run up to as fast as possible
+    pub seek: Option<usize>,
+}
+
+impl Playback {
+    pub const SPEEDS: [f32; 3] = [1., 2., 4.];
+
+    pub fn new(replay: Replay) -> Self {
+        Self {
+            replay,
+            tick: 0,
+            paused: false,
+            step: false,
+            speed: 0,
+            seek: None,
+        }
+    }
+
+    pub fn at_end(&self) -> bool {
+        self.tick >= self.replay.len()
+    }
+
+    /// the next tick runs on the next frame, whatever its timer says
+    pub fn forced(&self) -> bool {
+        !self.at_end() && (self.step || self.seek.is_some())
+    }
+
+    /// ticks follow their timer
+    pub fn running(&self) -> bool {
+        !self.at_end() && !self.paused
+    }
+
+    pub fn speed(&self) -> f32 {
+        Self::SPEEDS[self.speed]
+    }
+
+    pub fn next_speed(&mut self) {
+        self.speed = (self.speed + 1) % Self::SPEEDS.len();
+    }
+
+    /// the directions of the coming tick
+    pub fn next(&mut self) -> Vec<IVec2> {
+        let directions = self.replay.directions(self.tick);
+        self.tick += 1;
+        self.step = false;
+        if self.seek.is_some_and(|seek| seek <= self.tick) {
+            self.seek = None;
+        }
+        directions
+    }
+
+    /// whether the board is turned after the ticks played so far, as it
+    /// was in the recorded game
+    pub fn turned(&self) -> bool {
+        let turns = self.replay.turns.iter().filter(|tick| **tick <= self.tick).count();
+        self.replay.turned != (turns % 2 == 1)
+    }
+
+    /// true if reaching `tick` takes playing the game again from the start
+    pub fn seek(&mut self, tick: usize) -> bool {
+        let tick = tick.min(self.replay.len());
+        let restart = tick < self.tick;
+        self.seek = if tick == 0 || tick == self.tick { None } else { Some(tick) };
+        restart
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bot::Strategy,
+        resources::GameRng,
+        sim::SnakeSim,
+    };
+
+    fn replay() -> Replay {
+        Replay::new(42, &GameSetting::default(), &Campaign::default(), GameMode::Versus)
+    }
+
+    #[test]
+    fn directions_survive_the_encoding() {
+        let ticks = [
+            vec![UP, DOWN, LEFT],
+            vec![RIGHT, IVec2::ZERO, UP],
+            vec![IVec2::ZERO, LEFT, DOWN],
+        ];
+        let mut replay = replay();
+        for directions in &ticks {
+            replay.push(directions);
+        }
+        assert_eq!(replay.len(), ticks.len());
+        for (tick, directions) in ticks.iter().enumerate() {
+            assert_eq!(&replay.directions(tick), directions);
+        }
+        assert!(replay.directions(ticks.len()).is_empty());
+    }
+
+    /// a versus game with a bot, fruits drawn from `rng`, steered by the
+    /// bots or by `replay` when there is one, the board turns once midway
+    fn play(rng: &mut GameRng, replay: Option<&Replay>, recording: &mut Replay) -> SnakeSim {
+        let board = Board::new(9, 15);
+        let mut sim = SnakeSim::versus(board).with_free_rivals(1, 3);
+        sim.spawn_fruit(&mut rng.rng);
+        let strategies = [Strategy::Greedy, Strategy::AStar, Strategy::FloodFill];
+        let mut playback = replay.cloned().map(Playback::new);
+        let mut turned = false;
+        for tick in 0..300 {
+            // as orient does, between two ticks
+            let turn = match &playback {
+                Some(playback) => playback.turned() != turned,
+                None => tick == 40,
+            };
+            if turn {
+                turned = !turned;
+                sim = sim.turned(turned);
+                recording.turn();
+            }
+            let directions = match playback.as_mut() {
+                Some(playback) => playback.next(),
+                // the snakes wait a few ticks before the first move
+                None if tick < 3 => vec![IVec2::ZERO; 3],
+                None => strategies
+                    .iter()
+                    .enumerate()
+                    .map(|(i, strategy)| strategy.choose(&sim, i))
+                    .collect(),
+            };
+            sim.step(&directions);
+            if directions.iter().any(|direction| *direction != IVec2::ZERO) {
+                recording.push(&directions);
+            }
+            if sim.fruit().is_none() {
+                sim.spawn_fruit(&mut rng.rng);
+            }
+            if sim.snakes().iter().all(|snake| !snake.is_alive()) {
+                break;
+            }
+        }
+        sim
+    }
+
+    #[test]
+    fn a_replay_ends_where_the_game_did() {
+        let mut recorded = replay();
+        let live = play(&mut GameRng::new(recorded.seed), None, &mut recorded);
+        assert!(!recorded.is_empty());
+        // the first three ticks held still and were left out
+        assert_eq!(recorded.turns, vec![37]);
+        let mut again = replay();
+        let played = play(&mut GameRng::new(recorded.seed), Some(&recorded), &mut again);
+        assert_eq!(again.turns, recorded.turns);
+        assert_eq!(live.fruit(), played.fruit());
+        for (a, b) in live.snakes().iter().zip(played.snakes()) {
+            assert_eq!(a.body(), b.body());
+            assert_eq!(a.score(), b.score());
+            assert_eq!(a.is_alive(), b.is_alive());
+        }
+    }
+}
@@ -0,0 +1,310 @@
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use crate::{
+    prelude::*,
+    GameState,
+    MenuState,
+    GameAssets,
+    components::*,
+    resources::{GameSetting, GamepadSteer},
+    theme::{Palette, ThemeRole},
+    locale::Locale,
+    keymap::{KeyMap, Action},
+    input::gamepad_just_pressed,
+    menu_plugin::play_menu_setup,
+    replay::Playback,
+    sim::{UP, LEFT, RIGHT},
+};
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::New), playback_bar_setup
+                .after(play_menu_setup)
+                .run_if(resource_exists::<Playback>()))
+            .add_systems(Update, (
+                playback_buttons,
+                playback_keys,
+                scrub,
+                playback_texts.run_if(resource_changed::<Playback>()
+                    .or_else(resource_changed::<Locale>())),
+            )
+                .chain()
+                .run_if(resource_exists::<Playback>()
+                    .and_then(in_state(GameState::Play))));
+    }
+}
+
+/// pause, step, speed and exit buttons, the scrubber and the tick count
+/// along the bottom of the board
+fn playback_bar_setup(
+    mut commands: Commands,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>,
+    palette: Res<Palette>,
+    locale: Res<Locale>,
+    playback: Res<Playback>
+) {
+    let scale = game_settings.game_scale;
+    let button_text_style = TextStyle {
+        font_size: 20.0 * scale,
+        color: palette.text,
+        font: game_assets.cjk_font.clone(),
+    };
+    let button_style = Style {
+        width: Val::Px(50. * scale),
+        margin: UiRect::all(Val::Px(3. * scale)),
+        justify_content: JustifyContent::Center,
+        align_items: AlignItems::Center,
+        ..default()
+    };
+
+    commands
+        .spawn((NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(0.),
+                width: Val::Percent(100.0),
+                height: Val::Px(BOARD_OFFSET_Y * scale),
+                flex_direction: FlexDirection::Row,
+                align_items: AlignItems::Stretch,
+                ..default()
+            },
+            background_color: Color::rgba(0., 0., 0., 0.6).into(),
+            ..default()
+            },
+            OnPlaybackBar,
+            OnGameScreen,
+        ))
+        .with_children(|parent| {
+            for button in [PlaybackButton::Pause, PlaybackButton::Step, PlaybackButton::Speed] {
+                parent.spawn((
+                    ButtonBundle {
+                        style: button_style.clone(),
+                        background_color: palette.button.into(),
+                        ..default()
+                    },
+                    ThemeRole::Button,
+                    button,
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TextBundle::from_section(
+                            playback_label(Some(button), &playback, &locale),
+                            button_text_style.clone()
+                        ),
+                        ThemeRole::Text,
+                        PlaybackText(Some(button))
+                    ));
+                });
+            }
+            parent.spawn((
+                ButtonBundle {
+                    style: Style {
+                        flex_grow: 1.,
+                        margin: UiRect::all(Val::Px(12. * scale)),
+                        ..default()
+                    },
+                    background_color: palette.button.into(),
+                    ..default()
+                },
+                ThemeRole::Button,
+                Scrubber,
+                RelativeCursorPosition::default(),
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.),
+                            height: Val::Percent(100.),
+                            ..default()
+                        },
+                        background_color: palette.pressed_button.into(),
+                        ..default()
+                    },
+                    ThemeRole::Fill,
+                    ScrubberFill
+                ));
+            });
+            parent.spawn((
+                TextBundle::from_section(
+                    playback_label(None, &playback, &locale),
+                    button_text_style.clone()
+                ).with_style(Style {
+                    align_self: AlignSelf::Center,
+                    margin: UiRect::horizontal(Val::Px(3. * scale)),
+                    ..default()
+                }),
+                ThemeRole::Text,
+                PlaybackText(None)
+            ));
+            parent.spawn((
+                ButtonBundle {
+                    style: button_style,
+                    background_color: palette.button.into(),
+                    ..default()
+                },
+                ThemeRole::Button,
+                PlaybackButton::Exit,
+            ))
+            .with_children(|parent| {
+                parent.spawn((
+                    TextBundle::from_section(locale.get("quit"), button_text_style),
+                    ThemeRole::Text
+                ));
+            });
+        });
+}
+
+/// text of a playback button, the tick count for none
+fn playback_label(
+    button: Option<PlaybackButton>,
+    playback: &Playback,
+    locale: &Locale
+) -> String {
+    match button {
+        Some(PlaybackButton::Pause) =>
+            locale.get(if playback.paused { "play" } else { "pause" }).to_string(),
+        Some(PlaybackButton::Step) => locale.get("step").to_string(),
+        Some(PlaybackButton::Speed) => format!("{}x", playback.speed()),
+        Some(PlaybackButton::Exit) => locale.get("quit").to_string(),
+        None => format!("{}/{}", playback.tick, playback.replay.len()),
+    }
+}
+
+fn playback_texts(
+    mut texts: Query<(&mut Text, &PlaybackText)>,
+    mut fill_q: Query<&mut Style, With<ScrubberFill>>,
+    playback: Res<Playback>,
+    locale: Res<Locale>
+) {
+    for (mut text, PlaybackText(button)) in texts.iter_mut() {
+        let label = playback_label(*button, &playback, &locale);
+        if text.sections[0].value != label {
+            text.sections[0].value = label;
+        }
+    }
+    let done = playback.tick as f32 / playback.replay.len().max(1) as f32;
+    for mut style in fill_q.iter_mut() {
+        style.width = Val::Percent(100. * done.min(1.));
+    }
+}
+
+/// what a button or its key does to the playback
+fn press(
+    button: PlaybackButton,
+    commands: &mut Commands,
+    playback: &mut Playback,
+    game_settings: &mut GameSetting,
+    bar_q: &Query<Entity, With<OnPlaybackBar>>,
+    game_state: &mut NextState<GameState>,
+    menu_state: &mut NextState<MenuState>
+) {
+    match button {
+        PlaybackButton::Pause => playback.paused = !playback.paused,
+        PlaybackButton::Step => {
+            playback.paused = true;
+            playback.step = true;
+        },
+        PlaybackButton::Speed => playback.next_speed(),
+        PlaybackButton::Exit => {
+            // the player's own settings are back for the next game
+            game_settings.restore();
+            commands.remove_resource::<Playback>();
+            for bar in bar_q.iter() {
+                commands.entity(bar).despawn_recursive();
+            }
+            game_state.set(GameState::Menu);
+            menu_state.set(MenuState::Replays);
+        },
+    }
+}
+
+fn playback_buttons(
+    mut commands: Commands,
+    interaction_query: Query<
+        (&Interaction, &PlaybackButton),
+        (Changed<Interaction>, With<Button>)
+    >,
+    mut playback: ResMut<Playback>,
+    mut game_settings: ResMut<GameSetting>,
+    bar_q: Query<Entity, With<OnPlaybackBar>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>
+) {
+    for (interaction, button) in interaction_query.iter() {
+        if *interaction == Interaction::Pressed {
+            press(*button, &mut commands, &mut playback, &mut game_settings,
+                &bar_q, &mut game_state, &mut menu_state);
+        }
+    }
+}
+
+/// pause toggles, right steps a tick, up changes the speed, left goes back
+/// a tenth of the game and back leaves
+fn playback_keys(
+    mut commands: Commands,
+    kb_input: Res<Input<KeyCode>>,
+    keymap: Res<KeyMap>,
+    steer: Res<GamepadSteer>,
+    buttons: Res<Input<GamepadButton>>,
+    mut playback: ResMut<Playback>,
+    mut game_settings: ResMut<GameSetting>,
+    bar_q: Query<Entity, With<OnPlaybackBar>>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut menu_state: ResMut<NextState<MenuState>>
+) {
+    let steered = steer.direction(None).or(keymap.direction(None, &kb_input));
+    let button = if keymap.just_pressed(Action::Back, &kb_input) ||
+        gamepad_just_pressed(&buttons, GamepadButtonType::East) {
+        Some(PlaybackButton::Exit)
+    }
+    else if keymap.just_pressed(Action::Pause, &kb_input) ||
+        gamepad_just_pressed(&buttons, GamepadButtonType::Start) {
+        Some(PlaybackButton::Pause)
+    }
+    else if steered == Some(RIGHT) {
+        Some(PlaybackButton::Step)
+    }
+    else if steered == Some(UP) {
+        Some(PlaybackButton::Speed)
+    }
+    else {
+        None
+    };
+    if let Some(button) = button {
+        press(button, &mut commands, &mut playback, &mut game_settings,
+            &bar_q, &mut game_state, &mut menu_state);
+    }
+    else if steered == Some(LEFT) {
+        let back = (playback.replay.len() / 10).max(1);
+        let tick = playback.tick.saturating_sub(back);
+        if playback.seek(tick) {
+            game_state.set(GameState::New);
+        }
+    }
+}
+
+/// a click on the scrubber plays up to that point, from the start if it is
+/// behind
+fn scrub(
+    scrubber_q: Query<(&Interaction, &RelativeCursorPosition), With<Scrubber>>,
+    mut playback: ResMut<Playback>,
+    mut game_state: ResMut<NextState<GameState>>
+) {
+    for (interaction, cursor) in scrubber_q.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(position) = cursor.normalized else {
+            continue;
+        };
+        let tick = (position.x.clamp(0., 1.) * playback.replay.len() as f32).round() as usize;
+        if playback.seek(tick) {
+            game_state.set(GameState::New);
+        }
+    }
+}
@@ -1,17 +1,222 @@
+use std::{collections::HashMap, time::Duration};
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use rand::{thread_rng, Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use crate::{
+    sim::{Board, SnakeSim, DeathCause, Walls},
+    bot::Strategy,
+    level::{Level, Goal, LEVELS, CAMPAIGN},
+    theme::{Theme, THEMES},
+    locale::{Language, Strings, Locale},
+    prelude::*,
+    MenuState,
+    storage
+};
 
-#[derive(Default, Resource)]
-pub struct HighScore(pub u16);
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HighScoreEntry {
+    pub score: u32,
+    pub length: u32,
+    /// seconds survived
+    pub duration: f32,
+    pub difficulty: Difficulty,
+    /// seconds since the unix epoch
+    pub date: u64,
+}
+
+impl HighScoreEntry {
+    /// mm:ss survived
+    pub fn duration_label(&self) -> String {
+        let secs = self.duration as u32;
+        format!("{:02}:{:02}", secs / 60, secs % 60)
+    }
+
+    pub fn date_label(&self) -> String {
+        storage::date_label(self.date)
+    }
+}
+
+/// best games, highest score first, persisted across sessions
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+pub struct HighScores(pub Vec<HighScoreEntry>);
+
+impl HighScores {
+    pub const LEN: usize = 10;
+    const KEY: &'static str = "high_scores";
+
+    pub fn load() -> Self {
+        storage::load(Self::KEY).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::KEY, self);
+    }
+
+    /// add a finished game, returns its rank if it made the table
+    pub fn insert(&mut self, entry: HighScoreEntry) -> Option<usize> {
+        // equal scores are ranked by the shorter game
+        let rank = self.0
+            .iter()
+            .position(|e| entry.score > e.score ||
+                (entry.score == e.score && entry.duration < e.duration))
+            .unwrap_or(self.0.len());
+        if rank >= Self::LEN {
+            return None;
+        }
+        self.0.insert(rank, entry);
+        self.0.truncate(Self::LEN);
+        Some(rank)
+    }
+}
+
+/// summary of the finished game for the game over screen
+#[derive(Resource)]
+pub struct LastGame {
+    pub entry: HighScoreEntry,
+    pub cause: DeathCause,
+    /// place in the high score table, if it made it
+    pub rank: Option<usize>,
+}
+
+/// how many snakes the players steer
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Resource)]
+pub enum GameMode {
+    #[default]
+    Solo,
+    /// two players on one board, the round ends with the first death
+    Versus,
+}
+
+impl GameMode {
+    pub fn players(&self) -> usize {
+        match self {
+            GameMode::Solo => 1,
+            GameMode::Versus => 2,
+        }
+    }
+}
+
+/// outcome of a versus round for the round over screen
+#[derive(Resource)]
+pub struct RoundResult {
+    /// index of the surviving snake, none on a draw
+    pub winner: Option<usize>,
+    pub scores: Vec<u32>,
+}
+
+/// attract mode of the main menu, a bot plays behind it once nobody has
+/// touched anything for a while
+#[derive(Resource)]
+pub struct Demo {
+    pub idle: Timer,
+    pub playing: bool,
+}
+
+impl Demo {
+    pub const IDLE: Duration = Duration::from_secs(8);
+    pub const STRATEGY: Strategy = Strategy::FloodFill;
+
+    /// back to the interactive menu, counting the idle time anew
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.idle.reset();
+    }
+}
+
+impl Default for Demo {
+    fn default() -> Self {
+        Self {
+            idle: Timer::new(Self::IDLE, TimerMode::Once),
+            playing: false,
+        }
+    }
+}
+
+/// the campaign stage being played, endless games have none
+#[derive(Debug, Default, Resource)]
+pub struct Campaign {
+    /// index in `level::CAMPAIGN`
+    pub stage: Option<usize>,
+    /// goal of the running stage, read from its level when the game starts
+    pub goal: Option<Goal>,
+}
+
+/// stages cleared so far, persisted across sessions
+#[derive(Debug, Default, Resource, Serialize, Deserialize)]
+pub struct CampaignProgress {
+    pub cleared: usize,
+}
+
+impl CampaignProgress {
+    const KEY: &'static str = "campaign";
+
+    pub fn load() -> Self {
+        storage::load(Self::KEY).unwrap_or_default()
+    }
 
+    pub fn save(&self) {
+        storage::save(Self::KEY, self);
+    }
+
+    /// the stage to continue from, the first again once all are cleared
+    pub fn next_stage(&self) -> usize {
+        if self.is_complete() { 0 } else { self.cleared }
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.cleared >= CAMPAIGN.len()
+    }
+
+    /// remember `stage` as cleared
+    pub fn clear(&mut self, stage: usize) {
+        self.cleared = self.cleared.max(stage + 1);
+    }
+}
+
+/// time spent playing the current game, pauses excluded
 #[derive(Default, Resource)]
-pub struct SnakeSegments(pub Vec<Entity>);
+pub struct PlayTime(pub Duration);
+
+/// the rules of the running game, mirrored onto the sprites each tick
+#[derive(Resource)]
+pub struct Simulation(pub SnakeSim);
+
+impl Default for Simulation {
+    fn default() -> Self {
+        Self(SnakeSim::new(Board::new(BOARD_COLS, BOARD_ROWS)))
+    }
+}
+
+/// directions newly pushed this frame on each gamepad, by the D-pad or by
+/// the left stick leaving its deadzone or turning
+#[derive(Debug, Default, Resource)]
+pub struct GamepadSteer {
+    pub just_pressed: Vec<(Gamepad, IVec2)>,
+    /// where each left stick points, `IVec2::ZERO` inside the deadzone
+    pub sticks: HashMap<Gamepad, IVec2>,
+}
+
+impl GamepadSteer {
+    /// a direction pushed this frame on `gamepad`, or on any gamepad
+    pub fn direction(&self, gamepad: Option<Gamepad>) -> Option<IVec2> {
+        self.just_pressed
+            .iter()
+            .find(|(pad, _)| gamepad.is_none_or(|g| g == *pad))
+            .map(|(_, direction)| *direction)
+    }
+}
 
+// used by the alternative touch handler input::_touch_events
+#[allow(dead_code)]
 #[derive(Resource)]
 pub struct TouchPosition(pub Vec2);
 
 #[derive(Resource, Debug, PartialEq, Eq, Clone)]
 pub struct GameAssets {
     pub cjk_font: Handle<Font>,
+    /// asset directory of the snake and fruit images below
+    pub sprites: String,
     pub head_up: Handle<Image>,
     pub head_down: Handle<Image>,
     pub head_left: Handle<Image>,
@@ -27,6 +232,23 @@ pub struct GameAssets {
     pub tail_right: Handle<Image>,
     pub tail_up: Handle<Image>,
     pub apple: Handle<Image>,
+    pub golden_fruit: Handle<Image>,
+    pub shrink_fruit: Handle<Image>,
+    pub speed_fruit: Handle<Image>,
+    pub bonus_fruit: Handle<Image>,
+    pub sfx_eat: Handle<AudioSource>,
+    pub sfx_score: Handle<AudioSource>,
+    pub sfx_game_over: Handle<AudioSource>,
+    pub sfx_click: Handle<AudioSource>,
+    pub music: Handle<AudioSource>,
+    /// in the order of `level::LEVELS`
+    pub levels: Vec<Handle<Level>>,
+    /// in the order of `level::CAMPAIGN`
+    pub campaign: Vec<Handle<Level>>,
+    /// in the order of `theme::THEMES`
+    pub themes: Vec<Handle<Theme>>,
+    /// in the order of `Language::ALL`
+    pub locales: Vec<Handle<Strings>>,
 }
 
 impl GameAssets {
@@ -35,46 +257,645 @@ impl GameAssets {
         asset_server: Res<AssetServer>
 ) {
         let cjk_font = asset_server.load("fonts/NotoSansMonoCJKtc-Regular.otf");
-        let head_up = asset_server.load("textures/snake/head_up.png");
-        let head_down = asset_server.load("textures/snake/head_down.png");
-        let head_left = asset_server.load("textures/snake/head_left.png");
-        let head_right = asset_server.load("textures/snake/head_right.png");
-        let body_bottomleft = asset_server.load("textures/snake/body_bottomleft.png");
-        let body_bottomright = asset_server.load("textures/snake/body_bottomright.png");
-        let body_horizontal = asset_server.load("textures/snake/body_horizontal.png");
-        let body_topleft = asset_server.load("textures/snake/body_topleft.png");
-        let body_topright = asset_server.load("textures/snake/body_topright.png");
-        let body_vertical = asset_server.load("textures/snake/body_vertical.png");
-        let tail_down = asset_server.load("textures/snake/tail_down.png");
-        let tail_left = asset_server.load("textures/snake/tail_left.png");
-        let tail_right = asset_server.load("textures/snake/tail_right.png");
-        let tail_up = asset_server.load("textures/snake/tail_up.png");
-        let apple = asset_server.load("textures/snake/apple.png");
+        let sfx_eat = asset_server.load("sounds/eat.wav");
+        let sfx_score = asset_server.load("sounds/score.wav");
+        let sfx_game_over = asset_server.load("sounds/game_over.wav");
+        let sfx_click = asset_server.load("sounds/click.wav");
+        let music = asset_server.load("sounds/music.wav");
+        let levels = LEVELS
+            .iter()
+            .map(|(_, path)| asset_server.load(*path))
+            .collect();
+        let campaign = CAMPAIGN
+            .iter()
+            .map(|path| asset_server.load(*path))
+            .collect();
+        let themes = THEMES
+            .iter()
+            .map(|(_, path)| asset_server.load(*path))
+            .collect();
+        let locales = Language::ALL
+            .iter()
+            .map(|language| asset_server.load(language.path()))
+            .collect();
         
-        commands.insert_resource(
-            GameAssets {
-                cjk_font,
-                head_up, head_down, head_left, head_right,
-                body_bottomleft, body_bottomright, body_horizontal,
-                body_topleft, body_topright, body_vertical,
-                tail_down, tail_left, tail_right, tail_up,
-                apple,
-            }
-        );
+        let mut game_assets = GameAssets {
+            cjk_font,
+            sprites: String::new(),
+            head_up: default(), head_down: default(), head_left: default(),
+            head_right: default(),
+            body_bottomleft: default(), body_bottomright: default(),
+            body_horizontal: default(), body_topleft: default(),
+            body_topright: default(), body_vertical: default(),
+            tail_down: default(), tail_left: default(), tail_right: default(),
+            tail_up: default(),
+            apple: default(), golden_fruit: default(), shrink_fruit: default(),
+            speed_fruit: default(), bonus_fruit: default(),
+            sfx_eat, sfx_score, sfx_game_over, sfx_click, music,
+            levels,
+            campaign,
+            themes,
+            locales,
+        };
+        game_assets.load_sprites(&asset_server, "textures/snake");
+        commands.insert_resource(game_assets);
+    }
+
+    /// the snake and fruit images of the sprite set in `dir`
+    pub fn load_sprites(&mut self, asset_server: &AssetServer, dir: &str) {
+        let load = |name: &str| asset_server.load(format!("{dir}/{name}.png"));
+        self.head_up = load("head_up");
+        self.head_down = load("head_down");
+        self.head_left = load("head_left");
+        self.head_right = load("head_right");
+        self.body_bottomleft = load("body_bottomleft");
+        self.body_bottomright = load("body_bottomright");
+        self.body_horizontal = load("body_horizontal");
+        self.body_topleft = load("body_topleft");
+        self.body_topright = load("body_topright");
+        self.body_vertical = load("body_vertical");
+        self.tail_down = load("tail_down");
+        self.tail_left = load("tail_left");
+        self.tail_right = load("tail_right");
+        self.tail_up = load("tail_up");
+        self.apple = load("apple");
+        self.golden_fruit = load("golden");
+        self.shrink_fruit = load("shrink");
+        self.speed_fruit = load("speed");
+        self.bonus_fruit = load("bonus");
+        self.sprites = dir.to_string();
     }
 }
 
 #[derive(Resource, PartialEq, Clone)]
 pub struct GameSetting {
     pub game_scale: f32,
-    pub snake_width: f32
+    pub snake_width: f32,
+    pub board: Board,
+    pub walls: Walls,
+    /// index in `level::LEVELS`, an empty board if none
+    pub level: Option<usize>,
+    pub difficulty: Difficulty,
+    /// shorten the tick as the snake grows
+    pub accelerate: bool,
+    /// slide the snakes from cell to cell between ticks
+    pub smooth: bool,
+    /// volumes from 0 to 1, music and sound effects are scaled by master
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+    /// the gamepad steering each player, when unassigned a solo player
+    /// takes any gamepad and versus players the connected ones in order
+    pub gamepads: [Option<Gamepad>; 2],
+    /// computer snakes joining every game
+    pub bots: usize,
+    /// seed of every new game, a fresh one each game if none
+    pub seed: Option<u64>,
+    pub bot_strategy: Strategy,
+    /// index in `theme::THEMES`
+    pub theme: usize,
+    /// grid lines between the cells of the board
+    pub grid: bool,
+    /// of the menus and the play bar
+    pub language: Language,
+    /// follows the shape of the window
+    pub layout: Layout,
+    /// turn the board a quarter in a landscape window, a 9x15 board is
+    /// played as 15x9
+    pub turn_landscape: bool,
+    /// `board` and the running game are turned a quarter clockwise
+    pub turned: bool,
+    /// the player's own settings while a game is played with others, a
+    /// level's board or the rules of a continued game or a replay, they
+    /// are the ones saved and come back once the game is left
+    pub own: Option<Box<GameSetting>>,
+}
+
+impl GameSetting {
+    const KEY: &'static str = "settings";
+
+    /// the defaults with the settings of the last session
+    pub fn load() -> Self {
+        let mut game_settings = Self::default();
+        if let Some(preferences) = storage::load(Self::KEY) {
+            game_settings.apply(preferences);
+        }
+        game_settings
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::KEY, &self.preferences());
+    }
+
+    /// what is kept across sessions, the board as it is in a portrait window
+    pub fn preferences(&self) -> Preferences {
+        // the rules of a game played with other settings are not the player's
+        let rules = self.own.as_deref().unwrap_or(self);
+        Preferences {
+            board: if rules.turned { rules.board.turned() } else { rules.board },
+            walls: rules.walls,
+            level: rules.level,
+            difficulty: rules.difficulty,
+            accelerate: rules.accelerate,
+            smooth: self.smooth,
+            master_volume: self.master_volume,
+            music_volume: self.music_volume,
+            sfx_volume: self.sfx_volume,
+            muted: self.muted,
+            bots: rules.bots,
+            bot_strategy: rules.bot_strategy,
+            theme: self.theme,
+            grid: self.grid,
+            language: self.language,
+            turn_landscape: self.turn_landscape,
+        }
+    }
+
+    /// play with `settings` until the game is left, the player's own are
+    /// kept aside
+    pub fn play_with(&mut self, settings: GameSetting) {
+        let own = self.own.take().unwrap_or_else(|| Box::new(self.clone()));
+        *self = GameSetting { own: Some(own), ..settings };
+    }
+
+    /// change a rule of the game being played and the player's own alike
+    pub fn choose(&mut self, choice: impl Fn(&mut GameSetting)) {
+        choice(self);
+        if let Some(own) = self.own.as_deref_mut() {
+            choice(own);
+        }
+    }
+
+    /// the player's own rules back after a game played with others, the
+    /// board turned to the window as it is now
+    pub fn restore(&mut self) {
+        let Some(own) = self.own.take() else {
+            return;
+        };
+        self.board = if own.turned == self.turned { own.board } else { own.board.turned() };
+        self.walls = own.walls;
+        self.level = own.level;
+        self.difficulty = own.difficulty;
+        self.accelerate = own.accelerate;
+        self.bots = own.bots;
+        self.bot_strategy = own.bot_strategy;
+        self.seed = own.seed;
+    }
+
+    /// take the saved `preferences`, the ones out of range are left as they are
+    fn apply(&mut self, preferences: Preferences) {
+        let Preferences {
+            board, walls, level, difficulty, accelerate, smooth,
+            master_volume, music_volume, sfx_volume, muted,
+            bots, bot_strategy, theme, grid, language, turn_landscape
+        } = preferences;
+        if (BOARD_MIN_SIDE..=BOARD_MAX_COLS).contains(&board.cols) &&
+            (BOARD_MIN_SIDE..=BOARD_MAX_ROWS).contains(&board.rows) {
+            self.board = board;
+        }
+        if level.is_none_or(|i| i < LEVELS.len()) {
+            self.level = level;
+        }
+        if theme < THEMES.len() {
+            self.theme = theme;
+        }
+        self.walls = walls;
+        self.difficulty = difficulty;
+        self.accelerate = accelerate;
+        self.smooth = smooth;
+        self.master_volume = master_volume.clamp(0., 1.);
+        self.music_volume = music_volume.clamp(0., 1.);
+        self.sfx_volume = sfx_volume.clamp(0., 1.);
+        self.muted = muted;
+        self.bots = bots.min(BOTS_MAX);
+        self.bot_strategy = bot_strategy;
+        self.grid = grid;
+        self.language = language;
+        self.turn_landscape = turn_landscape;
+    }
+
+    /// board, walls and the score bar, never smaller than the classic
+    /// board so the menus still fit
+    pub fn window_size(&self) -> Vec2 {
+        let menus = self.menu_height();
+        match self.layout {
+            Layout::Portrait => {
+                let cols = self.board.cols.max(BOARD_COLS);
+                Vec2::new(
+                    cols as f32 * self.snake_width + 2. * WALL_WIDTH,
+                    (self.board.rows as f32 * self.snake_width + 2. * WALL_WIDTH +
+                        BOARD_OFFSET_Y).max(menus)
+                )
+            },
+            Layout::Landscape => Vec2::new(
+                self.board.cols as f32 * self.snake_width + 2. * WALL_WIDTH + HUD_WIDTH,
+                (self.board.rows as f32 * self.snake_width + 2. * WALL_WIDTH).max(menus)
+            )
+        }
+    }
+
+    /// height of the classic board, what the menus are made to fit
+    fn menu_height(&self) -> f32 {
+        BOARD_ROWS as f32 * self.snake_width + 2. * WALL_WIDTH + BOARD_OFFSET_Y
+    }
+
+    /// the scale at which the board, the walls and the score bar fill as
+    /// much of a window of `size` as they can
+    pub fn fit_scale(&self, size: Vec2) -> f32 {
+        let (cols, rows) = (self.board.cols as f32, self.board.rows as f32);
+        let (x, y) = match self.layout {
+            Layout::Portrait => (
+                (size.x - 2. * WALL_WIDTH) / (cols.max(BOARD_COLS as f32) * SNAKE_WIDTH),
+                (size.y - 2. * WALL_WIDTH - BOARD_OFFSET_Y) / (rows * SNAKE_WIDTH)
+            ),
+            Layout::Landscape => (
+                (size.x - 2. * WALL_WIDTH - HUD_WIDTH) / (cols * SNAKE_WIDTH),
+                (size.y - 2. * WALL_WIDTH) / (rows * SNAKE_WIDTH)
+            )
+        };
+        let menus = (size.y - 2. * WALL_WIDTH - BOARD_OFFSET_Y) /
+            (BOARD_ROWS as f32 * SNAKE_WIDTH);
+        x.min(y).min(menus).max(0.1)
+    }
+
+    /// where the middle of the board sits, out of the way of the score bar
+    pub fn board_offset(&self) -> Vec2 {
+        match self.layout {
+            Layout::Portrait => Vec2::new(0., -BOARD_OFFSET_Y / 2.),
+            Layout::Landscape => Vec2::new(HUD_WIDTH / 2., 0.),
+        }
+    }
+
+    pub fn music_level(&self) -> f32 {
+        if self.muted { 0. } else { self.master_volume * self.music_volume }
+    }
+
+    pub fn sfx_level(&self) -> f32 {
+        if self.muted { 0. } else { self.master_volume * self.sfx_volume }
+    }
 }
 
 impl Default for GameSetting {
     fn default() -> Self {
         Self { 
             game_scale: 1.,
-            snake_width: 40.
+            snake_width: SNAKE_WIDTH,
+            board: Board::new(BOARD_COLS, BOARD_ROWS),
+            walls: Walls::default(),
+            level: None,
+            difficulty: Difficulty::default(),
+            accelerate: false,
+            smooth: false,
+            master_volume: 1.,
+            music_volume: 0.5,
+            sfx_volume: 0.8,
+            muted: false,
+            gamepads: [None; 2],
+            bots: 0,
+            seed: None,
+            bot_strategy: Strategy::default(),
+            theme: 0,
+            grid: true,
+            language: Language::default(),
+            layout: Layout::default(),
+            turn_landscape: false,
+            turned: false,
+            own: None,
+        }
+    }
+}
+
+/// the settings kept with `storage`, ones missing from the saved file
+/// take their defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    pub board: Board,
+    pub walls: Walls,
+    pub level: Option<usize>,
+    pub difficulty: Difficulty,
+    pub accelerate: bool,
+    pub smooth: bool,
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    pub muted: bool,
+    pub bots: usize,
+    pub bot_strategy: Strategy,
+    pub theme: usize,
+    pub grid: bool,
+    pub language: Language,
+    pub turn_landscape: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        GameSetting::default().preferences()
+    }
+}
+
+/// the menu the settings screen goes back to, the main or the pause menu
+#[derive(Debug, Default, Resource)]
+pub struct SettingsReturn(pub MenuState);
+
+/// where the score bar goes, after the shape of the window
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    /// the score bar above the board
+    #[default]
+    Portrait,
+    /// the score bar on the left of the board
+    Landscape,
+}
+
+impl Layout {
+    /// the layout of a window of `size`
+    pub fn of(size: Vec2) -> Self {
+        if size.x > size.y { Layout::Landscape } else { Layout::Portrait }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Insane,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    /// time between two moves of the snake
+    pub fn tick(&self) -> Duration {
+        Duration::from_millis(match self {
+            Difficulty::Easy => 300,
+            Difficulty::Normal => 222,
+            Difficulty::Hard => 150,
+            Difficulty::Insane => 90,
+        })
+    }
+
+    /// tick for a snake of `len` segments, every fruit eaten makes it 3%
+    /// shorter down to half of the base tick when accelerating
+    pub fn tick_for(&self, len: usize, accelerate: bool) -> Duration {
+        if !accelerate {
+            return self.tick();
+        }
+        let eaten = len.saturating_sub(3) as i32;
+        self.tick().mul_f32(0.97_f32.powi(eaten).max(0.5))
+    }
+
+    pub fn next(&self) -> Self {
+        let i = Self::ALL.iter().position(|d| d == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    pub fn label<'a>(&self, locale: &'a Locale) -> &'a str {
+        locale.get(match self {
+            Difficulty::Easy => "difficulty_easy",
+            Difficulty::Normal => "difficulty_normal",
+            Difficulty::Hard => "difficulty_hard",
+            Difficulty::Insane => "difficulty_insane",
+        })
+    }
+}
+
+/// named board sizes, columns by rows, with the locale key of the name
+pub const BOARD_PRESETS: [(&str, Board); 4] = [
+    ("board_small", Board { cols: 7, rows: 11 }),
+    ("board_classic", Board { cols: BOARD_COLS, rows: BOARD_ROWS }),
+    ("board_wide", Board { cols: 12, rows: 12 }),
+    ("board_large", Board { cols: 13, rows: 17 }),
+];
+pub const BOARD_MIN_SIDE: u32 = 5;
+pub const BOARD_MAX_COLS: u32 = 15;
+pub const BOARD_MAX_ROWS: u32 = 19;
+pub const BOTS_MAX: usize = 3;
+
+/// randomness of a game, the same seed and the same moves play the same
+/// game again
+#[derive(Resource)]
+pub struct GameRng {
+    pub seed: u64,
+    /// what `StdRng` is, named so its position can be saved
+    pub rng: ChaCha12Rng,
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        Self { seed, rng: ChaCha12Rng::seed_from_u64(seed) }
+    }
+
+    /// the rng of `seed` after it gave what `position` says
+    pub fn at(seed: u64, position: u64) -> Self {
+        let mut rng = Self::new(seed);
+        rng.rng.set_word_pos(position.into());
+        rng
+    }
+
+    /// words drawn since the rng was seeded, no game gets near the end of
+    /// a u64
+    pub fn position(&self) -> u64 {
+        self.rng.get_word_pos() as u64
+    }
+
+    /// a fresh seed, short enough to read off the screen and type in
+    pub fn random_seed() -> u64 {
+        thread_rng().gen_range(0..1_000_000_000)
+    }
+
+    /// the seed of today's challenge, the same for everyone
+    pub fn daily_seed() -> u64 {
+        storage::unix_now() / 86_400
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(Self::random_seed())
+    }
+}
+
+/// faster moves for a while after eating a speed fruit
+#[derive(Default, Resource)]
+pub struct SpeedBoost(pub Option<Timer>);
+
+impl SpeedBoost {
+    pub const DURATION: Duration = Duration::from_secs(5);
+    /// scale of the tick while boosted
+    pub const FACTOR: f32 = 0.6;
+
+    pub fn start(&mut self) {
+        self.0 = Some(Timer::new(Self::DURATION, TimerMode::Once));
+    }
+}
+
+/// paces the moves of the snake, replaces a fixed `on_timer`
+#[derive(Resource)]
+pub struct TickTimer(pub Timer);
+
+impl Default for TickTimer {
+    fn default() -> Self {
+        Self(Timer::new(Difficulty::default().tick(), TimerMode::Repeating))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sim::{StepOutcome, UP, RIGHT};
+
+    /// a game on a portal board sweeping it row by row, a fruit drawn
+    /// from `rng` whenever the last one is eaten
+    fn play(rng: &mut GameRng) -> SnakeSim {
+        let mut sim = SnakeSim::new(Board::new(BOARD_COLS, BOARD_ROWS))
+            .with_walls(Walls::Portal);
+        sim.spawn_fruit(&mut rng.rng);
+        for tick in 0..200 {
+            let direction = if tick % BOARD_COLS == BOARD_COLS - 1 { UP } else { RIGHT };
+            sim.step(&[direction]);
+            if matches!(sim.snake(0).last_outcome(), StepOutcome::Ate(_)) {
+                sim.spawn_fruit(&mut rng.rng);
+            }
+        }
+        sim
+    }
+
+    #[test]
+    fn a_seed_plays_the_same_game() {
+        let (mut first_rng, mut second_rng) = (GameRng::new(42), GameRng::new(42));
+        let (first, second) = (play(&mut first_rng), play(&mut second_rng));
+        let (a, b) = (first.snake(0), second.snake(0));
+        assert!(a.score() > 0);
+        assert_eq!(a.body(), b.body());
+        assert_eq!(a.score(), b.score());
+        assert_eq!(first.fruit(), second.fruit());
+        assert_eq!(first.fruit_kind(), second.fruit_kind());
+        assert_eq!(first_rng.position(), second_rng.position());
+    }
+
+    #[test]
+    fn a_saved_position_continues_the_stream() {
+        let mut rng = GameRng::new(7);
+        for _ in 0..13 {
+            rng.rng.gen::<u32>();
+        }
+        let mut restored = GameRng::at(rng.seed, rng.position());
+        let ahead: Vec<u64> = (0..8).map(|_| rng.rng.gen()).collect();
+        let again: Vec<u64> = (0..8).map(|_| restored.rng.gen()).collect();
+        assert_eq!(ahead, again);
+    }
+
+    #[test]
+    fn a_level_game_keeps_the_players_board() {
+        let mut game_settings = GameSetting::default();
+        let preferences = game_settings.preferences();
+        // as init_snake plays the 12x12 corridors level
+        let level_board = Board::new(12, 12);
+        game_settings.play_with(GameSetting { board: level_board, ..game_settings.clone() });
+        assert_eq!(game_settings.board, level_board);
+        assert_eq!(game_settings.preferences().board, preferences.board);
+        // the next level keeps the player's board aside still
+        game_settings.play_with(GameSetting {
+            board: Board::new(10, 10),
+            ..game_settings.clone()
+        });
+        assert_eq!(game_settings.preferences(), preferences);
+        game_settings.restore();
+        assert_eq!(game_settings.board, preferences.board);
+        assert!(game_settings.own.is_none());
+    }
+
+    #[test]
+    fn a_continued_game_keeps_the_players_rules() {
+        let mut game_settings = GameSetting::default();
+        let preferences = game_settings.preferences();
+        game_settings.play_with(GameSetting {
+            walls: Walls::Portal,
+            level: Some(1),
+            bots: 2,
+            difficulty: Difficulty::Insane,
+            seed: Some(42),
+            ..game_settings.clone()
+        });
+        // the volume is the player's own, a rule changes for both
+        game_settings.sfx_volume = 0.3;
+        game_settings.choose(|settings| settings.accelerate = true);
+        assert_eq!(game_settings.preferences(), Preferences {
+            sfx_volume: 0.3,
+            accelerate: true,
+            ..preferences.clone()
+        });
+        game_settings.restore();
+        assert_eq!(game_settings.preferences(), Preferences {
+            sfx_volume: 0.3,
+            accelerate: true,
+            ..preferences
+        });
+        assert_eq!(game_settings.seed, None);
+    }
+
+    /// a normal game of `score` points lasting `duration` seconds
+    fn entry(score: u32, duration: f32) -> HighScoreEntry {
+        HighScoreEntry {
+            score,
+            length: 3,
+            duration,
+            difficulty: Difficulty::Normal,
+            date: 0,
+        }
+    }
+
+    fn scores(high_scores: &HighScores) -> Vec<(u32, f32)> {
+        high_scores.0.iter().map(|e| (e.score, e.duration)).collect()
+    }
+
+    #[test]
+    fn high_scores_go_highest_first() {
+        let mut high_scores = HighScores::default();
+        assert_eq!(high_scores.insert(entry(20, 60.)), Some(0));
+        assert_eq!(high_scores.insert(entry(50, 60.)), Some(0));
+        assert_eq!(high_scores.insert(entry(30, 60.)), Some(1));
+        assert_eq!(high_scores.insert(entry(10, 60.)), Some(3));
+        assert_eq!(scores(&high_scores), vec![(50, 60.), (30, 60.), (20, 60.), (10, 60.)]);
+    }
+
+    #[test]
+    fn an_equal_score_goes_to_the_shorter_game() {
+        let mut high_scores = HighScores::default();
+        high_scores.insert(entry(30, 60.));
+        assert_eq!(high_scores.insert(entry(30, 45.)), Some(0));
+        assert_eq!(high_scores.insert(entry(30, 90.)), Some(2));
+        // a game as short as one on the table ranks below it
+        assert_eq!(high_scores.insert(entry(30, 60.)), Some(2));
+        assert_eq!(scores(&high_scores), vec![(30, 45.), (30, 60.), (30, 60.), (30, 90.)]);
+    }
+
+    #[test]
+    fn the_table_keeps_the_best_len_games() {
+        let mut high_scores = HighScores::default();
+        for score in 1..=HighScores::LEN as u32 {
+            high_scores.insert(entry(score * 10, 60.));
         }
+        assert_eq!(high_scores.0.len(), HighScores::LEN);
+        // no better than the last one
+        assert_eq!(high_scores.insert(entry(5, 60.)), None);
+        assert_eq!(high_scores.insert(entry(10, 60.)), None);
+        // the best pushes the last one off
+        assert_eq!(high_scores.insert(entry(200, 60.)), Some(0));
+        assert_eq!(high_scores.0.len(), HighScores::LEN);
+        assert_eq!(high_scores.0[0].score, 200);
+        assert_eq!(high_scores.0.last().map(|e| e.score), Some(20));
+        // beating the last one by a shorter game still makes the table
+        assert_eq!(high_scores.insert(entry(20, 30.)), Some(HighScores::LEN - 1));
+        assert_eq!(high_scores.0.last().map(|e| e.duration), Some(30.));
     }
 }
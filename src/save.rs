@@ -0,0 +1,91 @@
+//! The game in progress, kept with `storage` when the player quits from the
+//! pause menu or closes the window, and continued from the main menu on the
+//! next start.
+
+use std::time::Duration;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::{
+    resources::{GameSetting, GameMode, Difficulty},
+    sim::SnakeSim,
+    bot::Strategy,
+    replay::Replay,
+    storage
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedGame {
+    /// the board, the snakes and the fruit
+    pub sim: SnakeSim,
+    /// seconds played so far
+    pub play_time: f32,
+    pub seed: u64,
+    /// see `GameRng::position`
+    pub rng_position: u64,
+    /// seconds left of a speed fruit's boost
+    pub speed_boost: Option<f32>,
+    /// index in `level::LEVELS`
+    pub level: Option<usize>,
+    /// campaign stage, it decides the level
+    pub stage: Option<usize>,
+    pub versus: bool,
+    pub bot_strategy: Strategy,
+    pub difficulty: Difficulty,
+    pub accelerate: bool,
+    /// played on a board turned for a landscape window
+    #[serde(default)]
+    pub turned: bool,
+    /// the replay being recorded, it goes on where it stopped
+    pub recording: Option<Replay>,
+}
+
+impl SavedGame {
+    pub fn play_time(&self) -> Duration {
+        Duration::from_secs_f32(self.play_time)
+    }
+
+    pub fn game_mode(&self) -> GameMode {
+        if self.versus { GameMode::Versus } else { GameMode::Solo }
+    }
+
+    /// `game_settings` changed to what the game was played with
+    pub fn settings(&self, game_settings: &GameSetting) -> GameSetting {
+        GameSetting {
+            board: self.sim.board(),
+            walls: self.sim.walls(),
+            level: self.level,
+            bots: self.sim.snakes().len() - self.game_mode().players(),
+            bot_strategy: self.bot_strategy,
+            difficulty: self.difficulty,
+            accelerate: self.accelerate,
+            turned: self.turned,
+            ..game_settings.clone()
+        }
+    }
+}
+
+/// the game saved on quitting, none once it is continued
+#[derive(Debug, Default, Resource)]
+pub struct SaveSlot(pub Option<SavedGame>);
+
+impl SaveSlot {
+    const KEY: &'static str = "saved_game";
+
+    pub fn load() -> Self {
+        Self(storage::load(Self::KEY))
+    }
+
+    pub fn save(&self) {
+        match &self.0 {
+            Some(game) => storage::save(Self::KEY, game),
+            None => storage::remove(Self::KEY),
+        }
+    }
+
+    /// the saved game, forgotten on disk as well so it is continued once
+    pub fn take(&mut self) -> Option<SavedGame> {
+        let game = self.0.take();
+        self.save();
+        game
+    }
+}
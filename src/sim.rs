@@ -0,0 +1,692 @@
+//! Headless snake rules.
+//!
+//! `SnakeSim` knows nothing about Bevy's ECS: the systems in `control` drive
+//! it one tick at a time and mirror its state onto sprites. Bots, tools and
+//! batch runs can use it directly without opening a window. Every snake on
+//! the board moves at once, the first one is the player's in a solo game.
+
+use std::collections::VecDeque;
+use bevy::math::{IVec2, Vec2};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+pub const UP: IVec2 = IVec2::new(0, 1);
+pub const DOWN: IVec2 = IVec2::new(0, -1);
+pub const LEFT: IVec2 = IVec2::new(-1, 0);
+pub const RIGHT: IVec2 = IVec2::new(1, 0);
+
+/// playing field in cells around (0, 0), an even side has one more cell
+/// below zero than above
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Board {
+    pub cols: u32,
+    pub rows: u32,
+}
+
+impl Board {
+    pub fn new(cols: u32, rows: u32) -> Self {
+        Self { cols, rows }
+    }
+
+    /// bottom left cell
+    pub fn min(&self) -> IVec2 {
+        IVec2::new(-(self.cols as i32 / 2), -(self.rows as i32 / 2))
+    }
+
+    /// top right cell
+    pub fn max(&self) -> IVec2 {
+        self.min() + IVec2::new(self.cols as i32 - 1, self.rows as i32 - 1)
+    }
+
+    /// middle of the board, half a cell off zero along an even side
+    pub fn center(&self) -> Vec2 {
+        (self.min() + self.max()).as_vec2() / 2.
+    }
+
+    pub fn contains(&self, cell: IVec2) -> bool {
+        let (min, max) = (self.min(), self.max());
+        min.x <= cell.x && cell.x <= max.x && min.y <= cell.y && cell.y <= max.y
+    }
+
+    /// step from `from` to the neighbouring `to`, across the edge of the
+    /// board when they sit on opposite sides
+    pub fn offset(&self, from: IVec2, to: IVec2) -> IVec2 {
+        let mut d = to - from;
+        let (cols, rows) = (self.cols as i32, self.rows as i32);
+        if d.x > 1 { d.x -= cols; } else if d.x < -1 { d.x += cols; }
+        if d.y > 1 { d.y -= rows; } else if d.y < -1 { d.y += rows; }
+        d
+    }
+
+    /// the cell opposite to `cell` across the middle of the board
+    pub fn mirror(&self, cell: IVec2) -> IVec2 {
+        self.min() + self.max() - cell
+    }
+
+    /// bring a cell off the board back in on the opposite side
+    pub fn wrap(&self, cell: IVec2) -> IVec2 {
+        let min = self.min();
+        let size = IVec2::new(self.cols as i32, self.rows as i32);
+        min + (cell - min).rem_euclid(size)
+    }
+
+    /// the board turned a quarter, columns become rows
+    pub fn turned(&self) -> Board {
+        Board::new(self.rows, self.cols)
+    }
+
+    /// where `cell` lands on `turned` when the board turns a quarter,
+    /// clockwise or back
+    pub fn turn(&self, cell: IVec2, clockwise: bool) -> IVec2 {
+        let (min, max) = (self.min(), self.max());
+        let turned = if clockwise {
+            IVec2::new(cell.y - min.y, max.x - cell.x)
+        } else {
+            IVec2::new(max.y - cell.y, cell.x - min.x)
+        };
+        self.turned().min() + turned
+    }
+
+    pub fn cell_count(&self) -> usize {
+        (self.cols * self.rows) as usize
+    }
+
+    /// every cell of the board, row by row from the bottom
+    pub fn cells(&self) -> impl Iterator<Item = IVec2> {
+        let (min, max) = (self.min(), self.max());
+        (min.y..=max.y).flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+    }
+}
+
+/// `direction` turned a quarter, clockwise or back
+pub fn turn_direction(direction: IVec2, clockwise: bool) -> IVec2 {
+    if clockwise {
+        IVec2::new(direction.y, -direction.x)
+    } else {
+        IVec2::new(-direction.y, direction.x)
+    }
+}
+
+/// what happens when the head leaves the board
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Walls {
+    #[default]
+    Solid,
+    /// the snake re-enters on the opposite side
+    Portal,
+}
+
+/// what a fruit does when eaten
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum FruitKind {
+    #[default]
+    Apple,
+    /// worth extra points
+    Golden,
+    /// takes tail segments off instead of growing the snake
+    Shrinking,
+    /// changes the pace for a while, the sim itself has no notion of time
+    Speed,
+    /// worth the most but taken off the board after a few moves
+    Bonus,
+}
+
+impl FruitKind {
+    pub const ALL: [FruitKind; 5] = [
+        FruitKind::Apple,
+        FruitKind::Golden,
+        FruitKind::Shrinking,
+        FruitKind::Speed,
+        FruitKind::Bonus,
+    ];
+    /// tail segments removed by a shrinking fruit
+    pub const SHRINK: usize = 2;
+    /// a shrinking fruit never makes the snake shorter than this
+    pub const MIN_LEN: usize = 3;
+
+    /// moves the fruit stays on the board, forever if none
+    pub fn lifetime(&self) -> Option<u32> {
+        match self {
+            FruitKind::Bonus => Some(24),
+            _ => None,
+        }
+    }
+
+    pub fn points(&self) -> u32 {
+        match self {
+            FruitKind::Apple | FruitKind::Shrinking | FruitKind::Speed => 1,
+            FruitKind::Golden => 3,
+            FruitKind::Bonus => 5,
+        }
+    }
+
+    /// relative chance to spawn next to a snake of `len` segments
+    pub fn weight(&self, len: usize) -> u32 {
+        match self {
+            FruitKind::Apple => 70,
+            FruitKind::Golden => 10,
+            // only once there is something to shrink
+            FruitKind::Shrinking if len > Self::MIN_LEN + Self::SHRINK => 8,
+            FruitKind::Shrinking => 0,
+            FruitKind::Speed => 8,
+            FruitKind::Bonus => 4,
+        }
+    }
+
+    /// a kind drawn by weight for a snake of `len` segments
+    pub fn pick<R: Rng + ?Sized>(rng: &mut R, len: usize) -> Self {
+        let total: u32 = Self::ALL.iter().map(|kind| kind.weight(len)).sum();
+        let mut roll = rng.gen_range(0..total);
+        for kind in Self::ALL {
+            let weight = kind.weight(len);
+            if roll < weight {
+                return kind;
+            }
+            roll -= weight;
+        }
+        FruitKind::Apple
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DeathCause {
+    Wall,
+    SelfCollision,
+    Obstacle,
+    /// ran into another snake, head on or into its body
+    Snake,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum StepOutcome {
+    /// the snake is held in place, nothing happened
+    #[default]
+    Idle,
+    Moved,
+    Ate(FruitKind),
+    Died(DeathCause),
+}
+
+/// one snake on the board, front of `body` is the head
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snake {
+    body: VecDeque<IVec2>,
+    direction: IVec2,
+    score: u32,
+    /// fruits eaten, unlike the score every kind counts one
+    eaten: u32,
+    alive: bool,
+    last_outcome: StepOutcome,
+}
+
+impl Snake {
+    /// `body` goes from the head to the tail and must be contiguous
+    fn new(body: impl IntoIterator<Item = IVec2>) -> Self {
+        let body: VecDeque<IVec2> = body.into_iter().collect();
+        assert!(body.len() >= 2, "a snake needs at least a head and a tail");
+        Self {
+            body,
+            direction: IVec2::ZERO,
+            score: 0,
+            eaten: 0,
+            alive: true,
+            last_outcome: StepOutcome::Idle,
+        }
+    }
+
+    pub fn body(&self) -> &VecDeque<IVec2> {
+        &self.body
+    }
+
+    pub fn head(&self) -> IVec2 {
+        self.body[0]
+    }
+
+    pub fn tail(&self) -> IVec2 {
+        self.body[self.body.len() - 1]
+    }
+
+    pub fn len(&self) -> usize {
+        self.body.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.body.is_empty()
+    }
+
+    pub fn direction(&self) -> IVec2 {
+        self.direction
+    }
+
+    pub fn score(&self) -> u32 {
+        self.score
+    }
+
+    pub fn fruits_eaten(&self) -> u32 {
+        self.eaten
+    }
+
+    pub fn is_alive(&self) -> bool {
+        self.alive
+    }
+
+    pub fn last_outcome(&self) -> StepOutcome {
+        self.last_outcome
+    }
+
+    pub fn occupies(&self, cell: IVec2) -> bool {
+        self.body.contains(&cell)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnakeSim {
+    board: Board,
+    walls: Walls,
+    /// cells inside the board that kill the snakes
+    obstacles: Vec<IVec2>,
+    snakes: Vec<Snake>,
+    fruit: Option<IVec2>,
+    fruit_kind: FruitKind,
+    /// moves before the fruit leaves the board
+    fruit_moves_left: Option<u32>,
+    /// the fruit left the board on the last step
+    fruit_expired: bool,
+}
+
+impl SnakeSim {
+    /// a three cells long snake in the middle of the board, facing right
+    pub fn new(board: Board) -> Self {
+        Self::with_snake(board, [IVec2::new(1, 0), IVec2::ZERO, IVec2::new(-1, 0)])
+    }
+
+    /// a single snake, `body` goes from the head to the tail and must be
+    /// contiguous
+    pub fn with_snake(board: Board, body: impl IntoIterator<Item = IVec2>) -> Self {
+        Self {
+            board,
+            walls: Walls::Solid,
+            obstacles: Vec::new(),
+            snakes: vec![Snake::new(body)],
+            fruit: None,
+            fruit_kind: FruitKind::Apple,
+            fruit_moves_left: None,
+            fruit_expired: false,
+        }
+    }
+
+    /// two snakes on opposite quarters of the board, facing each other
+    pub fn versus(board: Board) -> Self {
+        let (min, rows) = (board.min(), board.rows as i32);
+        let first: Vec<IVec2> = (0..3)
+            .map(|i| IVec2::new(min.x + 2 - i, min.y + rows / 4))
+            .collect();
+        let second: Vec<IVec2> = first.iter().map(|cell| board.mirror(*cell)).collect();
+        Self::with_snake(board, first).with_rival(second)
+    }
+
+    /// one more snake, after the ones already on the board
+    pub fn with_rival(mut self, body: impl IntoIterator<Item = IVec2>) -> Self {
+        self.snakes.push(Snake::new(body));
+        self
+    }
+
+    /// up to `count` more snakes of `len` cells wherever they fit, the
+    /// farthest from the first snake first
+    pub fn with_free_rivals(mut self, count: usize, len: usize) -> Self {
+        for _ in 0..count {
+            let Some(body) = self.free_spawn(len) else {
+                break;
+            };
+            self = self.with_rival(body);
+        }
+        self
+    }
+
+    /// a straight snake of `len` cells on free cells with two free cells
+    /// ahead of it, as far as possible from the head of the first snake
+    pub fn free_spawn(&self, len: usize) -> Option<Vec<IVec2>> {
+        let first = self.snakes[0].head();
+        let mut heads: Vec<IVec2> = self.board.cells().collect();
+        heads.sort_by_key(|cell| {
+            let d = (*cell - first).abs();
+            -(d.x + d.y)
+        });
+        heads
+            .into_iter()
+            .flat_map(|head| [LEFT, RIGHT, UP, DOWN].map(|direction| (head, direction)))
+            .map(|(head, direction)| (-2..len as i32)
+                .map(|i| head - direction * i)
+                .collect::<Vec<_>>())
+            .find(|cells| cells.iter().all(|cell| self.is_free(*cell)))
+            .map(|cells| cells[2..].to_vec())
+    }
+
+    pub fn with_walls(mut self, walls: Walls) -> Self {
+        self.walls = walls;
+        self
+    }
+
+    pub fn with_obstacles(mut self, obstacles: impl IntoIterator<Item = IVec2>) -> Self {
+        self.obstacles = obstacles.into_iter().collect();
+        self
+    }
+
+    /// the same game on the board turned a quarter, clockwise or back
+    pub fn turned(&self, clockwise: bool) -> Self {
+        let board = self.board;
+        let turn = |cell: IVec2| board.turn(cell, clockwise);
+        Self {
+            board: board.turned(),
+            obstacles: self.obstacles.iter().map(|cell| turn(*cell)).collect(),
+            snakes: self.snakes
+                .iter()
+                .map(|snake| Snake {
+                    body: snake.body.iter().map(|cell| turn(*cell)).collect(),
+                    direction: turn_direction(snake.direction, clockwise),
+                    ..snake.clone()
+                })
+                .collect(),
+            fruit: self.fruit.map(turn),
+            ..self.clone()
+        }
+    }
+
+    pub fn board(&self) -> Board {
+        self.board
+    }
+
+    pub fn obstacles(&self) -> &[IVec2] {
+        &self.obstacles
+    }
+
+    pub fn is_obstacle(&self, cell: IVec2) -> bool {
+        self.obstacles.contains(&cell)
+    }
+
+    pub fn walls(&self) -> Walls {
+        self.walls
+    }
+
+    pub fn snakes(&self) -> &[Snake] {
+        &self.snakes
+    }
+
+    pub fn snake(&self, index: usize) -> &Snake {
+        &self.snakes[index]
+    }
+
+    /// the length of the longest living snake
+    pub fn longest(&self) -> usize {
+        self.snakes
+            .iter()
+            .filter(|snake| snake.alive)
+            .map(Snake::len)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// the way the head of a snake actually faces, even before the first move
+    pub fn heading(&self, index: usize) -> IVec2 {
+        let body = &self.snakes[index].body;
+        self.board.offset(body[1], body[0])
+    }
+
+    /// true if `direction` would turn the head of a snake back into its neck
+    pub fn is_reverse(&self, index: usize, direction: IVec2) -> bool {
+        direction != IVec2::ZERO && direction == -self.heading(index)
+    }
+
+    pub fn fruit(&self) -> Option<IVec2> {
+        self.fruit
+    }
+
+    pub fn fruit_kind(&self) -> FruitKind {
+        self.fruit_kind
+    }
+
+    /// true if the fruit ran out of time on the last step, nobody ate it
+    pub fn fruit_expired(&self) -> bool {
+        self.fruit_expired
+    }
+
+    /// true if a living snake covers `cell`, dead ones leave the board
+    pub fn occupies(&self, cell: IVec2) -> bool {
+        self.snakes.iter().any(|snake| snake.alive && snake.occupies(cell))
+    }
+
+    /// on the board and neither an obstacle nor a snake
+    pub fn is_free(&self, cell: IVec2) -> bool {
+        self.board.contains(cell) && !self.is_obstacle(cell) && !self.occupies(cell)
+    }
+
+    /// cells where a fruit may be placed
+    pub fn free_cells(&self) -> Vec<IVec2> {
+        self.board
+            .cells()
+            .filter(|cell| !self.occupies(*cell) && !self.is_obstacle(*cell))
+            .collect()
+    }
+
+    /// place a plain apple, or take the fruit off the board
+    pub fn set_fruit(&mut self, cell: Option<IVec2>) {
+        self.fruit = cell;
+        self.fruit_kind = FruitKind::Apple;
+        self.fruit_moves_left = None;
+    }
+
+    /// place a fruit of a random kind on a random free cell, `None` if the
+    /// board is full
+    pub fn spawn_fruit<R: Rng + ?Sized>(&mut self, rng: &mut R) -> Option<IVec2> {
+        let free = self.free_cells();
+        self.fruit = if free.is_empty() {
+            None
+        } else {
+            Some(free[rng.gen_range(0..free.len())])
+        };
+        self.fruit_kind = FruitKind::pick(rng, self.longest());
+        self.fruit_moves_left = self.fruit_kind.lifetime();
+        self.fruit
+    }
+
+    /// advance every living snake one tick, `directions` by snake
+    ///
+    /// A reversing direction is ignored and the snake keeps its course,
+    /// `IVec2::ZERO` or a missing direction holds the snake in place.
+    pub fn step(&mut self, directions: &[IVec2]) {
+        // where every moving head goes
+        let mut next = vec![None; self.snakes.len()];
+        for (i, slot) in next.iter_mut().enumerate() {
+            if !self.snakes[i].alive {
+                continue;
+            }
+            let direction = directions.get(i).copied().unwrap_or(IVec2::ZERO);
+            if !self.is_reverse(i, direction) {
+                self.snakes[i].direction = direction;
+            }
+            let snake = &mut self.snakes[i];
+            if snake.direction == IVec2::ZERO {
+                snake.last_outcome = StepOutcome::Idle;
+                continue;
+            }
+            let mut cell = snake.head() + snake.direction;
+            if self.walls == Walls::Portal {
+                cell = self.board.wrap(cell);
+            }
+            *slot = Some(cell);
+        }
+
+        // a fruit with a lifetime runs out while the snakes move
+        self.fruit_expired = false;
+        if next.iter().any(Option::is_some) && self.fruit.is_some() {
+            match self.fruit_moves_left {
+                Some(left) if left <= 1 => {
+                    self.fruit = None;
+                    self.fruit_moves_left = None;
+                    self.fruit_expired = true;
+                },
+                Some(left) => self.fruit_moves_left = Some(left - 1),
+                None => ()
+            }
+        }
+
+        // a tail moves away this tick unless its snake holds still or grows
+        let grows = |cell: Option<IVec2>| cell.is_some() &&
+            cell == self.fruit &&
+            self.fruit_kind != FruitKind::Shrinking;
+        let taken = |j: usize, cell: IVec2| {
+            let snake = &self.snakes[j];
+            let tail_leaves = next[j].is_some() && !grows(next[j]);
+            snake.body
+                .iter()
+                .take(snake.body.len() - usize::from(tail_leaves))
+                .any(|c| *c == cell)
+        };
+        let deaths: Vec<Option<DeathCause>> = next
+            .iter()
+            .enumerate()
+            .map(|(i, cell)| {
+                let cell = (*cell)?;
+                if !self.board.contains(cell) {
+                    Some(DeathCause::Wall)
+                }
+                else if self.is_obstacle(cell) {
+                    Some(DeathCause::Obstacle)
+                }
+                else if taken(i, cell) {
+                    Some(DeathCause::SelfCollision)
+                }
+                // head on, or into the body of another snake
+                else if (0..self.snakes.len()).any(|j| j != i &&
+                    self.snakes[j].alive &&
+                    (next[j] == Some(cell) || taken(j, cell))) {
+                    Some(DeathCause::Snake)
+                }
+                else {
+                    None
+                }
+            })
+            .collect();
+
+        for (i, cell) in next.into_iter().enumerate() {
+            let Some(cell) = cell else {
+                continue;
+            };
+            let eating = self.fruit == Some(cell);
+            let snake = &mut self.snakes[i];
+            snake.last_outcome = if let Some(cause) = deaths[i] {
+                snake.alive = false;
+                StepOutcome::Died(cause)
+            }
+            else if eating {
+                let kind = self.fruit_kind;
+                snake.body.push_front(cell);
+                if kind == FruitKind::Shrinking {
+                    let len = (snake.body.len() - 1 - FruitKind::SHRINK)
+                        .max(FruitKind::MIN_LEN);
+                    snake.body.truncate(len);
+                }
+                self.fruit = None;
+                snake.score += kind.points();
+                snake.eaten += 1;
+                StepOutcome::Ate(kind)
+            }
+            else {
+                snake.body.push_front(cell);
+                snake.body.pop_back();
+                StepOutcome::Moved
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board() -> Board {
+        Board::new(9, 15)
+    }
+
+    fn cells(cells: &[(i32, i32)]) -> Vec<IVec2> {
+        cells.iter().map(|(x, y)| IVec2::new(*x, *y)).collect()
+    }
+
+    #[test]
+    fn dies_on_a_solid_wall() {
+        let mut sim = SnakeSim::with_snake(board(), cells(&[(4, 0), (3, 0), (2, 0)]));
+        sim.step(&[RIGHT]);
+        assert_eq!(sim.snake(0).last_outcome(), StepOutcome::Died(DeathCause::Wall));
+        assert!(!sim.snake(0).is_alive());
+    }
+
+    #[test]
+    fn dies_on_its_own_body() {
+        // heading left, up runs into the middle of the body
+        let mut sim = SnakeSim::with_snake(board(),
+            cells(&[(0, 0), (1, 0), (1, 1), (0, 1), (-1, 1)]));
+        sim.step(&[UP]);
+        assert_eq!(sim.snake(0).last_outcome(),
+            StepOutcome::Died(DeathCause::SelfCollision));
+    }
+
+    #[test]
+    fn dies_on_an_obstacle() {
+        let mut sim = SnakeSim::new(board()).with_obstacles([IVec2::new(2, 0)]);
+        sim.step(&[RIGHT]);
+        assert_eq!(sim.snake(0).last_outcome(), StepOutcome::Died(DeathCause::Obstacle));
+    }
+
+    #[test]
+    fn eats_and_grows() {
+        let mut sim = SnakeSim::new(board());
+        sim.set_fruit(Some(IVec2::new(2, 0)));
+        sim.step(&[RIGHT]);
+        let snake = sim.snake(0);
+        assert_eq!(snake.last_outcome(), StepOutcome::Ate(FruitKind::Apple));
+        assert_eq!(snake.len(), 4);
+        assert_eq!(snake.score(), 1);
+        assert_eq!(snake.tail(), IVec2::new(-1, 0));
+        assert_eq!(sim.fruit(), None);
+
+        // the growth stays, the tail follows again
+        sim.step(&[RIGHT]);
+        let snake = sim.snake(0);
+        assert_eq!(snake.last_outcome(), StepOutcome::Moved);
+        assert_eq!(snake.len(), 4);
+        assert_eq!(snake.head(), IVec2::new(3, 0));
+        assert_eq!(snake.tail(), IVec2::new(0, 0));
+    }
+
+    #[test]
+    fn follows_its_leaving_tail() {
+        let mut sim = SnakeSim::with_snake(board(), cells(&[(0, 0), (1, 0), (1, 1), (0, 1)]));
+        sim.step(&[UP]);
+        let snake = sim.snake(0);
+        assert_eq!(snake.last_outcome(), StepOutcome::Moved);
+        assert_eq!(snake.head(), IVec2::new(0, 1));
+        assert_eq!(snake.tail(), IVec2::new(1, 1));
+    }
+
+    #[test]
+    fn passes_through_a_portal_wall() {
+        let mut sim = SnakeSim::with_snake(board(), cells(&[(4, 0), (3, 0), (2, 0)]))
+            .with_walls(Walls::Portal);
+        sim.step(&[RIGHT]);
+        let snake = sim.snake(0);
+        assert_eq!(snake.last_outcome(), StepOutcome::Moved);
+        assert_eq!(snake.head(), IVec2::new(-4, 0));
+        assert_eq!(sim.heading(0), RIGHT);
+    }
+
+    #[test]
+    fn both_die_head_on() {
+        let mut sim = SnakeSim::with_snake(board(), cells(&[(-1, 0), (-2, 0), (-3, 0)]))
+            .with_rival(cells(&[(1, 0), (2, 0), (3, 0)]));
+        sim.step(&[RIGHT, LEFT]);
+        for snake in sim.snakes() {
+            assert_eq!(snake.last_outcome(), StepOutcome::Died(DeathCause::Snake));
+            assert!(!snake.is_alive());
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use bevy::prelude::*;
+use bevy::audio::Volume;
+use crate::{
+    GameState,
+    GameAssets,
+    components::BackgroundMusic,
+    resources::GameSetting,
+    events::*,
+};
+
+/// Sound effects for the game events and menus, and the background music
+/// while playing. Without an audio device bevy only logs a warning and the
+/// spawned sounds stay silent.
+pub struct SoundPlugin;
+
+impl Plugin for SoundPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(GameState::Play), play_music)
+            .add_systems(OnExit(GameState::Play), pause_music)
+            .add_systems(OnEnter(GameState::Over), stop_music)
+            .add_systems(Update, (
+                sound_effects,
+                music_volume.run_if(resource_changed::<GameSetting>()),
+            ));
+    }
+}
+
+fn play_sound(
+    commands: &mut Commands,
+    source: &Handle<AudioSource>,
+    game_settings: &GameSetting
+) {
+    let volume = game_settings.sfx_level();
+    if volume <= 0. {
+        return;
+    }
+    commands.spawn(AudioBundle {
+        source: source.clone(),
+        settings: PlaybackSettings::DESPAWN
+            .with_volume(Volume::new_relative(volume)),
+    });
+}
+
+fn sound_effects(
+    mut commands: Commands,
+    mut growth_evr: EventReader<GrowthEvent>,
+    mut shrink_evr: EventReader<ShrinkEvent>,
+    mut score_changed_evr: EventReader<ScoreChangedEvent>,
+    mut gameover_evr: EventReader<GameOverEvent>,
+    mut level_complete_evr: EventReader<LevelCompleteEvent>,
+    mut menu_action_evr: EventReader<MenuActionEvent>,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>
+) {
+    if growth_evr.read().count() > 0 {
+        play_sound(&mut commands, &game_assets.sfx_eat, &game_settings);
+    }
+    if shrink_evr.read().count() > 0 {
+        play_sound(&mut commands, &game_assets.sfx_eat, &game_settings);
+    }
+    if score_changed_evr.read().count() > 0 {
+        play_sound(&mut commands, &game_assets.sfx_score, &game_settings);
+    }
+    if level_complete_evr.read().count() > 0 {
+        play_sound(&mut commands, &game_assets.sfx_score, &game_settings);
+    }
+    if gameover_evr.read().count() > 0 {
+        play_sound(&mut commands, &game_assets.sfx_game_over, &game_settings);
+    }
+    if menu_action_evr.read().count() > 0 {
+        play_sound(&mut commands, &game_assets.sfx_click, &game_settings);
+    }
+}
+
+fn play_music(
+    mut commands: Commands,
+    music_q: Query<&AudioSink, With<BackgroundMusic>>,
+    game_assets: Res<GameAssets>,
+    game_settings: Res<GameSetting>
+) {
+    if let Ok(sink) = music_q.get_single() {
+        sink.play();
+        return;
+    }
+    commands.spawn((
+        AudioBundle {
+            source: game_assets.music.clone(),
+            settings: PlaybackSettings::LOOP
+                .with_volume(Volume::new_relative(game_settings.music_level())),
+        },
+        BackgroundMusic,
+    ));
+}
+
+fn pause_music(music_q: Query<&AudioSink, With<BackgroundMusic>>) {
+    if let Ok(sink) = music_q.get_single() {
+        sink.pause();
+    }
+}
+
+fn stop_music(
+    mut commands: Commands,
+    music_q: Query<Entity, With<BackgroundMusic>>
+) {
+    for entity in &music_q {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn music_volume(
+    music_q: Query<&AudioSink, With<BackgroundMusic>>,
+    game_settings: Res<GameSetting>
+) {
+    if let Ok(sink) = music_q.get_single() {
+        sink.set_volume(game_settings.music_level());
+    }
+}
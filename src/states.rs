@@ -5,6 +5,8 @@ pub enum GameState {
     #[default]
     Menu,
     New,
+    /// pick up the game saved when the last session quit
+    Continue,
     Resume,
     Play,
     Over,
@@ -17,5 +19,13 @@ pub enum MenuState {
     Main,
     Pause,
     Play,
+    HighScores,
+    GameOver,
+    Board,
+    LevelComplete,
+    Controls,
+    RoundOver,
+    Replays,
+    Settings,
 }
 
@@ -0,0 +1,170 @@
+//! Small key/value persistence: one RON file per key in the user data
+//! directory on native, `localStorage` in the browser.
+
+use serde::{de::DeserializeOwned, Serialize};
+use bevy::log::warn;
+
+/// read and deserialize the value stored under `key`
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let text = read(key)?;
+    match ron::from_str(&text) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("can not parse stored {key}: {err}");
+            None
+        }
+    }
+}
+
+/// serialize and store `value` under `key`, failures are only logged
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(text) => write(key, &text),
+        Err(err) => warn!("can not serialize {key}: {err}"),
+    }
+}
+
+/// forget the value stored under `key`
+pub fn remove(key: &str) {
+    erase(key);
+}
+
+/// yyyy-mm-dd in UTC of `unix` seconds
+pub fn date_label(unix: u64) -> String {
+    // civil from days, http://howardhinnant.github.io/date_algorithms.html
+    let z = (unix / 86400) as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// write `value` as `name.ron` into the folder `dir` next to the stored
+/// data, where the player can pick it up, the path written to on success
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export<T: Serialize>(dir: &str, name: &str, value: &T) -> Result<String, String> {
+    let text = ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default())
+        .map_err(|err| err.to_string())?;
+    let dir = data_dir().join(dir);
+    let path = dir.join(format!("{name}.ron"));
+    std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(&path, text))
+        .map_err(|err| err.to_string())?;
+    Ok(path.display().to_string())
+}
+
+/// every `.ron` file in the folder `dir` that parses, the others are
+/// only logged
+#[cfg(not(target_arch = "wasm32"))]
+pub fn import<T: DeserializeOwned>(dir: &str) -> Result<Vec<T>, String> {
+    let entries = std::fs::read_dir(data_dir().join(dir))
+        .map_err(|err| err.to_string())?;
+    let mut values = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.extension().is_none_or(|ext| ext != "ron") {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|text| ron::from_str(&text).map_err(|err| err.to_string()));
+        match parsed {
+            Ok(value) => values.push(value),
+            Err(err) => warn!("can not import {}: {err}", path.display()),
+        }
+    }
+    Ok(values)
+}
+
+/// the browser has no folder to share files through
+#[cfg(target_arch = "wasm32")]
+pub fn export<T: Serialize>(_dir: &str, _name: &str, _value: &T) -> Result<String, String> {
+    Err("not supported in the browser".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn import<T: DeserializeOwned>(_dir: &str) -> Result<Vec<T>, String> {
+    Err("not supported in the browser".to_string())
+}
+
+/// seconds since the unix epoch
+#[cfg(not(target_arch = "wasm32"))]
+pub fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// seconds since the unix epoch
+#[cfg(target_arch = "wasm32")]
+pub fn unix_now() -> u64 {
+    (js_sys::Date::now() / 1000.) as u64
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn data_dir() -> std::path::PathBuf {
+    use std::{env, path::PathBuf};
+    let base = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".local").join("share")))
+        .unwrap_or_else(|| PathBuf::from("."));
+    base.join("snake_game_bevy")
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(data_dir().join(format!("{key}.ron"))).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, text: &str) {
+    let dir = data_dir();
+    if let Err(err) = std::fs::create_dir_all(&dir)
+        .and_then(|_| std::fs::write(dir.join(format!("{key}.ron")), text)) {
+        warn!("can not store {key}: {err}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn erase(key: &str) {
+    let path = data_dir().join(format!("{key}.ron"));
+    if path.exists() {
+        if let Err(err) = std::fs::remove_file(path) {
+            warn!("can not remove {key}: {err}");
+        }
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(key).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, text: &str) {
+    let stored = local_storage()
+        .map(|storage| storage.set_item(key, text).is_ok())
+        .unwrap_or(false);
+    if !stored {
+        warn!("can not store {key} in localStorage");
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn erase(key: &str) {
+    if let Some(storage) = local_storage() {
+        let _ = storage.remove_item(key);
+    }
+}
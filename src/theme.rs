@@ -0,0 +1,143 @@
+//! Looks of the game stored as RON under `assets/themes/`: the colours of
+//! the board and the menus, and the directory holding the snake and fruit
+//! sprites.
+//!
+//! ```ron
+//! (
+//!     sprites: "textures/snake",
+//!     palette: (
+//!         text: Rgba(red: 0.9, green: 0.9, blue: 0.9, alpha: 1.0),
+//!         button: Rgba(red: 0.15, green: 0.15, blue: 0.15, alpha: 1.0),
+//!         ...
+//!     ),
+//! )
+//! ```
+//! The sprite directory has the same file names as `textures/snake`.
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    reflect::TypePath,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+
+/// locale key of the name and asset path of the shipped themes
+pub const THEMES: [(&str, &str); 3] = [
+    ("theme_classic", "themes/classic.theme.ron"),
+    ("theme_high_contrast", "themes/high_contrast.theme.ron"),
+    ("theme_dark", "themes/dark.theme.ron"),
+];
+
+/// colours of the board and the menus, the current ones are a resource
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize, Resource)]
+pub struct Palette {
+    pub text: Color,
+    pub button: Color,
+    /// a hovered or selected button
+    pub hovered_button: Color,
+    /// a selected button under the pointer
+    pub hovered_pressed_button: Color,
+    pub pressed_button: Color,
+    /// behind the buttons of a menu
+    pub panel: Color,
+    pub wall: Color,
+    pub portal: Color,
+    pub grid: Color,
+    /// the window around the board
+    pub background: Color,
+}
+
+impl Default for Palette {
+    /// the classic theme, shown until the chosen theme is loaded
+    fn default() -> Self {
+        Self {
+            text: Color::rgb(0.9, 0.9, 0.9),
+            button: Color::rgb(0.15, 0.15, 0.15),
+            hovered_button: Color::rgb(0.25, 0.25, 0.25),
+            hovered_pressed_button: Color::rgb(0.25, 0.65, 0.25),
+            pressed_button: Color::rgb(0.35, 0.75, 0.35),
+            panel: Color::CRIMSON,
+            wall: Color::LIME_GREEN,
+            portal: Color::CYAN,
+            grid: Color::DARK_GRAY,
+            background: ClearColor::default().0,
+        }
+    }
+}
+
+impl Palette {
+    /// colour of a button pressed, under the pointer or picked with the
+    /// keys
+    pub fn button_color(&self, interaction: Interaction, selected: bool) -> Color {
+        match (interaction, selected) {
+            (Interaction::Pressed, _) => self.pressed_button,
+            (Interaction::None, true) => self.hovered_button,
+            (Interaction::Hovered, true) => self.hovered_pressed_button,
+            (Interaction::Hovered, false) => self.hovered_button,
+            (Interaction::None, false) => self.button,
+        }
+    }
+
+    /// colour of an entity playing `role`, a button at rest
+    pub fn color(&self, role: ThemeRole) -> Color {
+        match role {
+            ThemeRole::Button => self.button,
+            ThemeRole::Panel => self.panel,
+            ThemeRole::Text => self.text,
+            ThemeRole::Fill => self.pressed_button,
+            ThemeRole::Wall => self.wall,
+            ThemeRole::Portal => self.portal,
+            ThemeRole::Grid => self.grid,
+        }
+    }
+}
+
+/// the palette colour an entity is drawn in, it is coloured again by it
+/// when the theme changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum ThemeRole {
+    /// coloured by its interaction and whether it is selected
+    Button,
+    /// behind the buttons of a menu
+    Panel,
+    Text,
+    /// the played part of the replay scrubber
+    Fill,
+    Wall,
+    Portal,
+    Grid,
+}
+
+#[derive(Debug, Deserialize, Asset, TypePath)]
+pub struct Theme {
+    /// asset directory of the snake and fruit images
+    pub sprites: String,
+    pub palette: Palette,
+}
+
+#[derive(Default)]
+pub struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    type Asset = Theme;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Theme, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}